@@ -0,0 +1,172 @@
+help-header = These commands are supported:
+cmd-help = shows this text.
+cmd-checkanime = checks if there are any anime updates, "dryrun" only shows what would be notified.
+cmd-updateanime = updates the viewing progress of a series, a tag only offers the series that have it.
+cmd-showfollowinganime = shows the animes that we are following, "season" groups them by season and "by name|season|progress|updated" sorts them.
+cmd-showfinishedanime = shows the animes that we have finished, "by name|season|progress|updated" sorts them.
+cmd-towatch = gives a to-watch list to catch up on, "by name|season|progress|updated|oldest|remaining|priority" sorts it.
+cmd-finishanime = marks a given anime as finished, a tag only offers the series that have it.
+cmd-genid = generates an id for a given name.
+cmd-language = sets the language of the bot for this chat.
+cmd-import = imports a MyAnimeList or AniList export.
+cmd-export = exports our lists as xml, csv or json.
+cmd-schedule = shows the episodes expected this week.
+cmd-airing = sets when a series airs every week.
+cmd-timezone = sets the timezone of this chat.
+cmd-season = shows our series of a season, the current one by default, "by name|season|progress|updated" sorts them.
+cmd-digest = sets the day of the weekly digest, "off" disables it and "now" sends it right away.
+cmd-stats = shows how much we have watched and what is left.
+cmd-duration = sets how many minutes the episodes of a series last, "off" goes back to 24.
+cmd-priority = sets the priority of a series in /towatch: high, normal or low.
+cmd-whoswatched = shows up to which episode of a series each of us has watched.
+cmd-rate = gives a series that we finished a score from 1 to 10.
+cmd-note = writes a note about a series that we finished, "off" removes it.
+cmd-tag = adds a tag to a series, or removes it if the series already has it.
+cmd-list = shows the series that have a tag, or our tags if none is given, "by name|season|progress|updated" sorts them.
+
+invalid-state = Unable to handle the message. Type /help to see the usage.
+
+update-prompt = Which anime do you want to update?
+not-following = I couldn't find { $anime } in our follows
+updated-episode = Updated { $title } to episode { $episode }
+progress-changed = { $title } is already at episode { $episode }, nothing was changed.
+
+following-empty = We are not following any anime series.
+following-header = We are following these anime series:
+finished-empty = We haven't finished any anime series.
+finished-header = We have finished these anime series:
+
+finish-prompt = Which anime have you finished?
+already-finished = You already have { $anime } in our finished list
+finished-added = { $title } has been added to the finished list.
+outdated-button = This button is outdated, please send the command again.
+prompt-handled = This was already answered.
+
+towatch-header = This is our watchlist:
+towatch-totals = { $episodes } episodes of { $series } series, about { $hours } hours.
+towatch-empty = We are up to date according to the latest Update data.
+towatch-single = just Ep. { $episode }
+towatch-range = from { $from } up to Ep.{ $to }
+
+no-updates = There are no updates!
+updates-header = This is the latest anime update:
+update-line = — { $episodes } for { $title } is out ({ $name })
+new-series-header = We have new series coming up!
+
+language-current = The language of this chat is { $language }. Available languages: { $available }.
+language-set = I will talk in English from now on.
+language-unknown = I don't know the language "{ $language }". Available languages: { $available }.
+
+import-prompt = Send me the MyAnimeList XML or the AniList JSON export as a document.
+import-invalid = I couldn't read the export: { $error }
+import-summary = Imported { $following } series to follow and { $finished } finished ones. { $existing } were already in our lists and { $skipped } have a status that we don't track.
+import-unmapped-header = These couldn't be matched with a series of the feeds:
+
+export-unknown = I can't export as "{ $format }". Available formats: { $available }.
+
+checkanime-usage = Use /checkanime, or /checkanime dryrun to see what would be notified without recording it.
+dryrun-header = Dry run: nothing will be notified or recorded.
+dryrun-source = { $source }: { $count } releases
+dryrun-not-followed = not followed
+dryrun-premiere = not followed, new series
+dryrun-other-variant = we follow the { $variant } release
+dryrun-up-to-date = up to date, last known Ep. { $episode }
+dryrun-new = new { $episodes } for { $title }
+dryrun-notify-header = Would notify:
+
+timezone-current = The timezone of this chat is { $timezone }. Set another one with its name, e.g. /timezone Europe/Madrid.
+timezone-set = Times will be shown in { $timezone } from now on.
+timezone-unknown = I don't know the timezone "{ $timezone }", use a name like Europe/Madrid.
+
+schedule-empty = No series has an airing time yet, set them with /airing.
+schedule-header = Expected over the next week ({ $timezone }):
+schedule-line = · { $airing } — { $title } Ep. { $episode } (in { $countdown })
+airing-usage = Use /airing <weekday> <HH:MM> [timezone], e.g. /airing sat 23:30 Asia/Tokyo, or /airing off to forget it. The timezone of the chat is used if none is given.
+airing-prompt = Which series airs on { $airing }?
+airing-clear-prompt = Which series doesn't have an airing time anymore?
+airing-set = { $title } airs on { $airing }.
+airing-cleared = { $title } doesn't have an airing time anymore.
+late-header = These episodes aired but haven't appeared yet:
+late-line = — { $title }, aired on { $airing }
+
+weekday-mon = Monday
+weekday-tue = Tuesday
+weekday-wed = Wednesday
+weekday-thu = Thursday
+weekday-fri = Friday
+weekday-sat = Saturday
+weekday-sun = Sunday
+
+showfollowing-usage = Use /showfollowinganime, or /showfollowinganime season to group the series by season, optionally followed by "by name|season|progress|updated".
+season-unknown = I don't understand the season "{ $season }", use something like /season Winter 2025.
+season-empty = We have no series of { $season }.
+season-header = Our series of { $season }:
+season-following = Following:
+season-finished = Finished:
+sort-unknown = I can't sort by "{ $order }". Available orders: { $available }.
+
+digest-current = The weekly digest is sent on { $day } at { $hour }:00 ({ $timezone }).
+digest-off = There is no weekly digest, choose its day with e.g. /digest sunday.
+digest-set = The weekly digest will be sent on { $day } at { $hour }:00 ({ $timezone }).
+digest-disabled = The weekly digest won't be sent anymore.
+digest-unknown = I don't know the day "{ $day }", use something like /digest sunday, /digest off or /digest now.
+digest-weekly-header = Our week in anime:
+digest-season-header = Our { $season } in anime:
+digest-released = { $episodes } new episodes of the series that we follow:
+digest-watched = We watched { $episodes } episodes:
+digest-finished = We finished:
+digest-quiet = Nothing was released nor watched.
+digest-backlog = { $episodes } episodes of { $series } series are waiting to be watched.
+digest-backlog-empty = We are up to date with every series.
+
+stats-header = Our statistics:
+stats-series = Following { $following } series and finished { $finished }.
+stats-watched = We watched { $episodes } episodes, about { $hours } hours.
+stats-seasons = Series per season:
+stats-backlog = Left to watch: { $episodes } episodes of { $series } series, about { $hours } hours.
+stats-backlog-empty = Nothing left to watch.
+duration-usage = Use /duration <minutes>, e.g. /duration 12, or /duration off to go back to { $default } minutes.
+duration-prompt = Which series has episodes of { $minutes } minutes?
+duration-clear-prompt = Which series has episodes of the usual length?
+duration-set = Episodes of { $title } last { $minutes } minutes.
+duration-cleared = Episodes of { $title } last the usual { $minutes } minutes.
+priority-usage = Use /priority high, /priority normal or /priority low.
+priority-prompt = Which series gets { $priority } priority?
+priority-set = { $title } now has { $priority } priority.
+priority-high = high
+priority-normal = normal
+priority-low = low
+watched-button = ✓ { $title } Ep. { $episode }
+watched-marked = { $member } watched { $title } up to Ep. { $episode }.
+whoswatched-usage = Use /whoswatched <series>, e.g. /whoswatched Frieren.
+whoswatched-unknown = We have no series called "{ $series }".
+whoswatched-ambiguous = Several series match, which one do you mean?
+whoswatched-header = Where each of us is in { $title }:
+whoswatched-together = — Together: Ep. { $episode }
+whoswatched-member = — { $member }: Ep. { $episode }
+whoswatched-nobody = Nobody marked an episode as watched yet.
+whoswatched-released = Released up to Ep. { $episode }.
+rate-usage = Use /rate <score> with a score from 1 to 10, e.g. /rate 8.
+rate-prompt = Which series gets { $score }/10?
+rate-finished = How would you rate it?
+rated = We gave { $title } { $score }/10.
+thumbs-prompt = Did you like it?
+thumb-up = 👍 for Ep. { $episode } of { $title }.
+thumb-down = 👎 for Ep. { $episode } of { $title }.
+note-usage = Use /note <text> to write a note about a series that we finished, or /note off to remove it.
+note-too-long = Notes can't be longer than { $max } characters.
+note-prompt = Which series is the note about?
+note-clear-prompt = Which series doesn't need its note anymore?
+note-set = Noted for { $title }.
+note-cleared = { $title } has no note anymore.
+tag-usage = Use /tag <tag> to add a tag to a series or remove it, e.g. /tag watch with Ana.
+tag-invalid = Tags can't be empty nor longer than { $max } characters.
+tag-prompt = Which series gets the tag "{ $tag }", or loses it?
+tag-added = { $title } is tagged { $tag } now.
+tag-removed = { $title } isn't tagged { $tag } anymore.
+tag-no-series = No series is tagged { $tag }.
+tags-header = Our tags:
+tags-none = We haven't tagged any series yet, /tag does it.
+list-header = Series tagged { $tag }:
+list-following = Following:
+list-finished = Finished:
@@ -0,0 +1,172 @@
+help-header = Estos son los comandos disponibles:
+cmd-help = muestra este texto.
+cmd-checkanime = comprueba si hay novedades de anime, con "dryrun" solo muestra qué se notificaría.
+cmd-updateanime = actualiza el progreso de una serie, con una etiqueta solo ofrece las series que la tienen.
+cmd-showfollowinganime = muestra los animes que estamos siguiendo, "season" los agrupa por temporada y "by name|season|progress|updated" los ordena.
+cmd-showfinishedanime = muestra los animes que hemos terminado, "by name|season|progress|updated" los ordena.
+cmd-towatch = da una lista de pendientes para ponernos al día, "by name|season|progress|updated|oldest|remaining|priority" la ordena.
+cmd-finishanime = marca un anime como terminado, con una etiqueta solo ofrece las series que la tienen.
+cmd-genid = genera un id para un nombre dado.
+cmd-language = cambia el idioma del bot en este chat.
+cmd-import = importa una exportación de MyAnimeList o AniList.
+cmd-export = exporta nuestras listas como xml, csv o json.
+cmd-schedule = muestra los episodios previstos para esta semana.
+cmd-airing = indica cuándo se emite una serie cada semana.
+cmd-timezone = establece la zona horaria de este chat.
+cmd-season = muestra nuestras series de una temporada, la actual por defecto, "by name|season|progress|updated" las ordena.
+cmd-digest = establece el día del resumen semanal, "off" lo desactiva y "now" lo envía ahora mismo.
+cmd-stats = muestra cuánto hemos visto y cuánto queda.
+cmd-duration = establece cuántos minutos duran los episodios de una serie, "off" vuelve a 24.
+cmd-priority = establece la prioridad de una serie en /towatch: high, normal o low.
+cmd-whoswatched = muestra hasta qué episodio de una serie ha visto cada uno.
+cmd-rate = da a una serie que hemos terminado una nota del 1 al 10.
+cmd-note = escribe un comentario sobre una serie que hemos terminado, "off" lo quita.
+cmd-tag = pone una etiqueta a una serie, o se la quita si ya la tiene.
+cmd-list = muestra las series que tienen una etiqueta, o nuestras etiquetas si no se da ninguna, "by name|season|progress|updated" las ordena.
+
+invalid-state = No puedo procesar el mensaje. Escribe /help para ver el uso.
+
+update-prompt = ¿Qué anime quieres actualizar?
+not-following = No he encontrado { $anime } entre los que seguimos
+updated-episode = { $title } actualizado al episodio { $episode }
+progress-changed = { $title } ya está en el episodio { $episode }, no he cambiado nada.
+
+following-empty = No estamos siguiendo ninguna serie de anime.
+following-header = Estamos siguiendo estas series de anime:
+finished-empty = No hemos terminado ninguna serie de anime.
+finished-header = Hemos terminado estas series de anime:
+
+finish-prompt = ¿Qué anime habéis terminado?
+already-finished = { $anime } ya está en nuestra lista de terminados
+finished-added = { $title } se ha añadido a la lista de terminados.
+outdated-button = Este botón está caducado, vuelve a enviar el comando.
+prompt-handled = Esto ya está respondido.
+
+towatch-header = Esta es nuestra lista de pendientes:
+towatch-totals = { $episodes } episodios de { $series } series, unas { $hours } horas.
+towatch-empty = Estamos al día según los últimos datos de novedades.
+towatch-single = solo el Ep. { $episode }
+towatch-range = del { $from } hasta el Ep. { $to }
+
+no-updates = ¡No hay novedades!
+updates-header = Estas son las últimas novedades de anime:
+update-line = — Ha salido { $episodes } de { $title } ({ $name })
+new-series-header = ¡Vienen series nuevas!
+
+language-current = El idioma de este chat es { $language }. Idiomas disponibles: { $available }.
+language-set = A partir de ahora hablaré en castellano.
+language-unknown = No conozco el idioma «{ $language }». Idiomas disponibles: { $available }.
+
+import-prompt = Envíame la exportación XML de MyAnimeList o JSON de AniList como documento.
+import-invalid = No he podido leer la exportación: { $error }
+import-summary = Importadas { $following } series a seguir y { $finished } terminadas. { $existing } ya estaban en nuestras listas y { $skipped } tienen un estado que no seguimos.
+import-unmapped-header = Estas no coinciden con ninguna serie de los feeds:
+
+export-unknown = No puedo exportar como «{ $format }». Formatos disponibles: { $available }.
+
+checkanime-usage = Usa /checkanime, o /checkanime dryrun para ver qué se notificaría sin registrarlo.
+dryrun-header = Simulación: no se notificará ni registrará nada.
+dryrun-source = { $source }: { $count } lanzamientos
+dryrun-not-followed = no la seguimos
+dryrun-premiere = no la seguimos, serie nueva
+dryrun-other-variant = seguimos la versión { $variant }
+dryrun-up-to-date = al día, último conocido Ep. { $episode }
+dryrun-new = nuevo { $episodes } de { $title }
+dryrun-notify-header = Se notificaría:
+
+timezone-current = La zona horaria de este chat es { $timezone }. Indica otra por su nombre, p. ej. /timezone Europe/Madrid.
+timezone-set = A partir de ahora mostraré las horas en { $timezone }.
+timezone-unknown = No conozco la zona horaria «{ $timezone }», usa un nombre como Europe/Madrid.
+
+schedule-empty = Ninguna serie tiene hora de emisión, indícalas con /airing.
+schedule-header = Previsto para la próxima semana ({ $timezone }):
+schedule-line = · { $airing } — { $title } Ep. { $episode } (en { $countdown })
+airing-usage = Usa /airing <día> <HH:MM> [zona horaria], p. ej. /airing sat 23:30 Asia/Tokyo, o /airing off para olvidarla. Si no indicas la zona horaria se usa la del chat.
+airing-prompt = ¿Qué serie se emite el { $airing }?
+airing-clear-prompt = ¿Qué serie ya no tiene hora de emisión?
+airing-set = { $title } se emite el { $airing }.
+airing-cleared = { $title } ya no tiene hora de emisión.
+late-header = Estos episodios ya se emitieron pero aún no han aparecido:
+late-line = — { $title }, emitido el { $airing }
+
+weekday-mon = lunes
+weekday-tue = martes
+weekday-wed = miércoles
+weekday-thu = jueves
+weekday-fri = viernes
+weekday-sat = sábado
+weekday-sun = domingo
+
+showfollowing-usage = Usa /showfollowinganime, o /showfollowinganime season para agrupar las series por temporada, opcionalmente seguido de "by name|season|progress|updated".
+season-unknown = No entiendo la temporada «{ $season }», usa algo como /season Winter 2025.
+season-empty = No tenemos series de { $season }.
+season-header = Nuestras series de { $season }:
+season-following = Siguiendo:
+season-finished = Terminadas:
+sort-unknown = No sé ordenar por «{ $order }». Órdenes disponibles: { $available }.
+
+digest-current = El resumen semanal se envía el { $day } a las { $hour }:00 ({ $timezone }).
+digest-off = No hay resumen semanal, elige su día con p. ej. /digest sunday.
+digest-set = El resumen semanal se enviará el { $day } a las { $hour }:00 ({ $timezone }).
+digest-disabled = Ya no se enviará el resumen semanal.
+digest-unknown = No conozco el día «{ $day }», usa algo como /digest sunday, /digest off o /digest now.
+digest-weekly-header = Nuestra semana de anime:
+digest-season-header = Nuestro anime de { $season }:
+digest-released = { $episodes } episodios nuevos de las series que seguimos:
+digest-watched = Hemos visto { $episodes } episodios:
+digest-finished = Hemos terminado:
+digest-quiet = No ha salido ni hemos visto nada.
+digest-backlog = Quedan por ver { $episodes } episodios de { $series } series.
+digest-backlog-empty = Estamos al día con todas las series.
+
+stats-header = Nuestras estadísticas:
+stats-series = Seguimos { $following } series y hemos terminado { $finished }.
+stats-watched = Hemos visto { $episodes } episodios, unas { $hours } horas.
+stats-seasons = Series por temporada:
+stats-backlog = Por ver: { $episodes } episodios de { $series } series, unas { $hours } horas.
+stats-backlog-empty = No queda nada por ver.
+duration-usage = Usa /duration <minutos>, p. ej. /duration 12, o /duration off para volver a { $default } minutos.
+duration-prompt = ¿Qué serie tiene episodios de { $minutes } minutos?
+duration-clear-prompt = ¿Qué serie tiene episodios de la duración habitual?
+duration-set = Los episodios de { $title } duran { $minutes } minutos.
+duration-cleared = Los episodios de { $title } duran los { $minutes } minutos habituales.
+priority-usage = Usa /priority high, /priority normal o /priority low.
+priority-prompt = ¿Qué serie tiene prioridad { $priority }?
+priority-set = { $title } tiene ahora prioridad { $priority }.
+priority-high = alta
+priority-normal = normal
+priority-low = baja
+watched-button = ✓ { $title } Ep. { $episode }
+watched-marked = { $member } ha visto { $title } hasta el Ep. { $episode }.
+whoswatched-usage = Usa /whoswatched <serie>, p. ej. /whoswatched Frieren.
+whoswatched-unknown = No tenemos ninguna serie llamada «{ $series }».
+whoswatched-ambiguous = Coinciden varias series, ¿cuál quieres decir?
+whoswatched-header = Por dónde va cada uno en { $title }:
+whoswatched-together = — Juntos: Ep. { $episode }
+whoswatched-member = — { $member }: Ep. { $episode }
+whoswatched-nobody = Nadie ha marcado aún ningún episodio como visto.
+whoswatched-released = Publicada hasta el Ep. { $episode }.
+rate-usage = Usa /rate <nota> con una nota del 1 al 10, p. ej. /rate 8.
+rate-prompt = ¿Qué serie se lleva un { $score }/10?
+rate-finished = ¿Qué nota le damos?
+rated = Le hemos dado a { $title } un { $score }/10.
+thumbs-prompt = ¿Te ha gustado?
+thumb-up = 👍 para el Ep. { $episode } de { $title }.
+thumb-down = 👎 para el Ep. { $episode } de { $title }.
+note-usage = Usa /note <texto> para escribir un comentario sobre una serie que hemos terminado, o /note off para quitarlo.
+note-too-long = Los comentarios no pueden tener más de { $max } caracteres.
+note-prompt = ¿Sobre qué serie es el comentario?
+note-clear-prompt = ¿Qué serie ya no necesita su comentario?
+note-set = Apuntado para { $title }.
+note-cleared = { $title } ya no tiene comentario.
+tag-usage = Usa /tag <etiqueta> para poner una etiqueta a una serie o quitársela, p. ej. /tag ver con Ana.
+tag-invalid = Las etiquetas no pueden estar vacías ni tener más de { $max } caracteres.
+tag-prompt = ¿Qué serie recibe la etiqueta «{ $tag }», o la pierde?
+tag-added = { $title } tiene ahora la etiqueta { $tag }.
+tag-removed = { $title } ya no tiene la etiqueta { $tag }.
+tag-no-series = Ninguna serie tiene la etiqueta { $tag }.
+tags-header = Nuestras etiquetas:
+tags-none = Aún no hemos etiquetado ninguna serie, /tag lo hace.
+list-header = Series con la etiqueta { $tag }:
+list-following = Siguiendo:
+list-finished = Terminadas:
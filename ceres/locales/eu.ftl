@@ -0,0 +1,172 @@
+help-header = Hauek dira onartutako komandoak:
+cmd-help = testu hau erakusten du.
+cmd-checkanime = anime berritasunik dagoen egiaztatzen du, "dryrun"-ekin zer jakinaraziko litzatekeen erakusten du soilik.
+cmd-updateanime = serie baten ikuste-aurrerapena eguneratzen du, etiketa batekin hura duten serieak bakarrik eskaintzen ditu.
+cmd-showfollowinganime = jarraitzen ari garen animeak erakusten ditu, "season"-ekin denboraldika taldekatzen ditu eta "by name|season|progress|updated"-ekin ordenatzen ditu.
+cmd-showfinishedanime = amaitu ditugun animeak erakusten ditu, "by name|season|progress|updated"-ekin ordenatzen ditu.
+cmd-towatch = egunean jartzeko ikusi beharrekoen zerrenda ematen du, "by name|season|progress|updated|oldest|remaining|priority"-ekin ordenatzen du.
+cmd-finishanime = anime bat amaitutzat markatzen du, etiketa batekin hura duten serieak bakarrik eskaintzen ditu.
+cmd-genid = izen baterako id bat sortzen du.
+cmd-language = txat honetako hizkuntza aldatzen du.
+cmd-import = MyAnimeList edo AniList esportazio bat inportatzen du.
+cmd-export = gure zerrendak xml, csv edo json gisa esportatzen ditu.
+cmd-schedule = aste honetan espero diren atalak erakusten ditu.
+cmd-airing = serie bat astero noiz ematen den ezartzen du.
+cmd-timezone = txat honen ordu-eremua ezartzen du.
+cmd-season = denboraldi bateko gure serieak erakusten ditu, unekoarenak lehenetsita, "by name|season|progress|updated"-ekin ordenatzen ditu.
+cmd-digest = asteko laburpenaren eguna ezartzen du, "off"-ek desgaitzen du eta "now"-ek berehala bidaltzen du.
+cmd-stats = zenbat ikusi dugun eta zenbat falta den erakusten du.
+cmd-duration = serie baten atalek zenbat minutu irauten duten ezartzen du, "off"-ek 24ra itzultzen du.
+cmd-priority = serie baten lehentasuna ezartzen du /towatch-en: high, normal edo low.
+cmd-whoswatched = serie baten zein ataleraino ikusi duen bakoitzak erakusten du.
+cmd-rate = amaitu dugun serie bati 1etik 10erako nota ematen dio.
+cmd-note = amaitu dugun serie bati buruzko ohar bat idazten du, "off"-ek kentzen du.
+cmd-tag = serie bati etiketa bat jartzen dio, edo kentzen dio jada badu.
+cmd-list = etiketa bat duten serieak erakusten ditu, edo gure etiketak bat ematen ez bada, "by name|season|progress|updated"-ekin ordenatzen ditu.
+
+invalid-state = Ezin dut mezua kudeatu. Idatzi /help erabilera ikusteko.
+
+update-prompt = Zein anime eguneratu nahi duzu?
+not-following = Ez dut { $anime } aurkitu jarraitzen ditugunen artean
+updated-episode = { $title } { $episode }. atalera eguneratu da
+progress-changed = { $title } dagoeneko { $episode }. atalean dago, ez da ezer aldatu.
+
+following-empty = Ez gara anime seriorik jarraitzen ari.
+following-header = Anime serie hauek jarraitzen ari gara:
+finished-empty = Ez dugu anime seriorik amaitu.
+finished-header = Anime serie hauek amaitu ditugu:
+
+finish-prompt = Zein anime amaitu duzue?
+already-finished = { $anime } dagoeneko amaitutakoen zerrendan dago
+finished-added = { $title } amaitutakoen zerrendara gehitu da.
+outdated-button = Botoi hau zaharkituta dago, bidali komandoa berriro.
+prompt-handled = Honi dagoeneko erantzun zaio.
+
+towatch-header = Hau da gure ikusteko zerrenda:
+towatch-totals = { $series } serieren { $episodes } atal, { $hours } ordu inguru.
+towatch-empty = Egunean gaude azken eguneraketen datuen arabera.
+towatch-single = { $episode }. atala bakarrik
+towatch-range = { $from }. ataletik { $to }. atalera arte
+
+no-updates = Ez dago berritasunik!
+updates-header = Hauek dira azken anime berritasunak:
+update-line = — { $title }: { $episodes } atera da ({ $name })
+new-series-header = Serie berriak datoz!
+
+language-current = Txat honetako hizkuntza { $language } da. Hizkuntza erabilgarriak: { $available }.
+language-set = Hemendik aurrera euskaraz hitz egingo dut.
+language-unknown = Ez dut "{ $language }" hizkuntza ezagutzen. Hizkuntza erabilgarriak: { $available }.
+
+import-prompt = Bidali MyAnimeList-en XML edo AniList-en JSON esportazioa dokumentu gisa.
+import-invalid = Ezin izan dut esportazioa irakurri: { $error }
+import-summary = Jarraitzeko { $following } serie eta amaitutako { $finished } inportatu dira. { $existing } gure zerrendetan zeuden jada eta { $skipped } jarraitzen ez dugun egoera batean daude.
+import-unmapped-header = Hauek ez datoz bat jarioetako inongo seriekin:
+
+export-unknown = Ezin dut «{ $format }» gisa esportatu. Formatu erabilgarriak: { $available }.
+
+checkanime-usage = Erabili /checkanime, edo /checkanime dryrun zer jakinaraziko litzatekeen ikusteko, ezer gorde gabe.
+dryrun-header = Simulazioa: ez da ezer jakinaraziko ez gordeko.
+dryrun-source = { $source }: { $count } argitalpen
+dryrun-not-followed = ez dugu jarraitzen
+dryrun-premiere = ez dugu jarraitzen, serie berria
+dryrun-other-variant = { $variant } bertsioa jarraitzen dugu
+dryrun-up-to-date = egunean, azken ezaguna Ep. { $episode }
+dryrun-new = { $episodes } berria { $title }-(e)rako
+dryrun-notify-header = Hau jakinaraziko litzateke:
+
+timezone-current = Txat honen ordu-eremua { $timezone } da. Ezarri beste bat bere izenarekin, adib. /timezone Europe/Madrid.
+timezone-set = Hemendik aurrera orduak { $timezone } ordu-eremuan erakutsiko ditut.
+timezone-unknown = Ez dut "{ $timezone }" ordu-eremua ezagutzen, erabili Europe/Madrid bezalako izen bat.
+
+schedule-empty = Serie batek ere ez du emisio-ordurik, ezarri itzazu /airing erabiliz.
+schedule-header = Hurrengo asterako espero dena ({ $timezone }):
+schedule-line = · { $airing } — { $title } { $episode }. atala ({ $countdown } barru)
+airing-usage = Erabili /airing <eguna> <HH:MM> [ordu-eremua], adib. /airing sat 23:30 Asia/Tokyo, edo /airing off ahazteko. Ordu-eremurik ematen ez bada txatarena erabiltzen da.
+airing-prompt = Zein serie ematen da { $airing }?
+airing-clear-prompt = Zein seriek ez du emisio-ordurik jada?
+airing-set = { $title } { $airing } ematen da.
+airing-cleared = { $title } serieak ez du emisio-ordurik jada.
+late-header = Atal hauek eman dira baina oraindik ez dira agertu:
+late-line = — { $title }, { $airing } emana
+
+weekday-mon = astelehena
+weekday-tue = asteartea
+weekday-wed = asteazkena
+weekday-thu = osteguna
+weekday-fri = ostirala
+weekday-sat = larunbata
+weekday-sun = igandea
+
+showfollowing-usage = Erabili /showfollowinganime, edo /showfollowinganime season serieak denboraldika taldekatzeko, aukeran "by name|season|progress|updated" gehituta.
+season-unknown = Ez dut "{ $season }" denboraldia ulertzen, erabili /season Winter 2025 bezalako zerbait.
+season-empty = Ez dugu { $season } denboraldiko serierik.
+season-header = { $season } denboraldiko gure serieak:
+season-following = Jarraitzen:
+season-finished = Amaituak:
+sort-unknown = Ezin dut "{ $order }" arabera ordenatu. Ordena erabilgarriak: { $available }.
+
+digest-current = Asteko laburpena { $day }(e)an bidaltzen da { $hour }:00etan ({ $timezone }).
+digest-off = Ez dago asteko laburpenik, aukeratu bere eguna adib. /digest sunday erabiliz.
+digest-set = Asteko laburpena { $day }(e)an bidaliko da { $hour }:00etan ({ $timezone }).
+digest-disabled = Asteko laburpena ez da gehiago bidaliko.
+digest-unknown = Ez dut "{ $day }" eguna ezagutzen, erabili /digest sunday, /digest off edo /digest now bezalako zerbait.
+digest-weekly-header = Gure anime astea:
+digest-season-header = Gure { $season } animea:
+digest-released = Jarraitzen ditugun serieen { $episodes } atal berri:
+digest-watched = { $episodes } atal ikusi ditugu:
+digest-finished = Amaitu ditugu:
+digest-quiet = Ez da ezer atera eta ez dugu ezer ikusi.
+digest-backlog = { $series } serieren { $episodes } atal daude ikusteko zain.
+digest-backlog-empty = Serie guztiekin egunean gaude.
+
+stats-header = Gure estatistikak:
+stats-series = { $following } serie jarraitzen ditugu eta { $finished } amaitu ditugu.
+stats-watched = { $episodes } atal ikusi ditugu, { $hours } ordu inguru.
+stats-seasons = Serieak denboraldika:
+stats-backlog = Ikusteko: { $series } serieren { $episodes } atal, { $hours } ordu inguru.
+stats-backlog-empty = Ez dago ezer ikusteko.
+duration-usage = Erabili /duration <minutuak>, adib. /duration 12, edo /duration off { $default } minutura itzultzeko.
+duration-prompt = Zein seriek ditu { $minutes } minutuko atalak?
+duration-clear-prompt = Zein seriek ditu ohiko iraupeneko atalak?
+duration-set = { $title } serieko atalek { $minutes } minutu irauten dute.
+duration-cleared = { $title } serieko atalek ohiko { $minutes } minutuak irauten dute.
+priority-usage = Erabili /priority high, /priority normal edo /priority low.
+priority-prompt = Zein seriek du { $priority } lehentasuna?
+priority-set = { $title } serieak { $priority } lehentasuna du orain.
+priority-high = goi
+priority-normal = ohiko
+priority-low = behe
+watched-button = ✓ { $title } { $episode }. atala
+watched-marked = { $member }(e)k { $title } { $episode }. ataleraino ikusi du.
+whoswatched-usage = Erabili /whoswatched <seriea>, adib. /whoswatched Frieren.
+whoswatched-unknown = Ez dugu "{ $series }" izeneko serierik.
+whoswatched-ambiguous = Hainbat serie datoz bat, zein esan nahi duzu?
+whoswatched-header = Bakoitza non doan { $title } seriean:
+whoswatched-together = — Elkarrekin: { $episode }. atala
+whoswatched-member = — { $member }: { $episode }. atala
+whoswatched-nobody = Inork ez du oraindik atalik ikusitzat markatu.
+whoswatched-released = { $episode }. ataleraino argitaratua.
+rate-usage = Erabili /rate <nota> 1etik 10erako nota batekin, adib. /rate 8.
+rate-prompt = Zein seriek jasotzen du { $score }/10?
+rate-finished = Zer nota emango diogu?
+rated = { $title } serieari { $score }/10 eman diogu.
+thumbs-prompt = Gustatu zaizu?
+thumb-up = 👍 { $title } serieko { $episode }. atalarentzat.
+thumb-down = 👎 { $title } serieko { $episode }. atalarentzat.
+note-usage = Erabili /note <testua> amaitu dugun serie bati buruzko ohar bat idazteko, edo /note off kentzeko.
+note-too-long = Oharrek ezin dute { $max } karaktere baino gehiago izan.
+note-prompt = Zein serieri buruzkoa da oharra?
+note-clear-prompt = Zein seriek ez du bere oharra behar jada?
+note-set = { $title } seriearentzat idatzita.
+note-cleared = { $title } serieak ez du oharrik jada.
+tag-usage = Erabili /tag <etiketa> serie bati etiketa bat jarri edo kentzeko, adib. /tag Anerekin ikusteko.
+tag-invalid = Etiketak ezin dira hutsik egon ezta { $max } karaktere baino luzeagoak izan ere.
+tag-prompt = Zein seriek jasotzen du "{ $tag }" etiketa, edo galtzen du?
+tag-added = { $title } serieak { $tag } etiketa du orain.
+tag-removed = { $title } serieak ez du { $tag } etiketa jada.
+tag-no-series = Ez dago { $tag } etiketa duen serierik.
+tags-header = Gure etiketak:
+tags-none = Oraindik ez dugu serierik etiketatu, /tag-ek egiten du.
+list-header = { $tag } etiketa duten serieak:
+list-following = Jarraitzen:
+list-finished = Amaituak:
@@ -1,20 +1,117 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use slug::slugify;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::schedule::Airing;
 
 pub const ANIME_RSS: &str = "https://raw.githubusercontent.com/ArjixGamer/gogoanime-rss/main/gogoanime/gogoanime-rss-sub.xml";
+pub const ANIME_RSS_DUB: &str = "https://raw.githubusercontent.com/ArjixGamer/gogoanime-rss/main/gogoanime/gogoanime-rss-dub.xml";
+pub const RSS_FEEDS: [(&str, AnimeVariant); 2] = [
+    (ANIME_RSS, AnimeVariant::Sub),
+    (ANIME_RSS_DUB, AnimeVariant::Dub),
+];
 pub const ANIME_RAW: &str = "https://gogoanime3.co/";
+// how many pages of the recent releases listing the scraper may walk through
+pub const SCRAP_MAX_PAGES: u16 = 5;
+// length of an episode unless the series says otherwise
+pub const DEFAULT_EPISODE_MINUTES: u16 = 24;
+// scores go from 1 to this
+pub const MAX_SCORE: u8 = 10;
+// longest note that we keep on a series, in characters, so that it fits in a list
+pub const NOTE_MAX_CHARS: usize = 500;
+// how long the history of the updates is kept, enough for the seasonal digests
+const HISTORY_KEPT: Duration = Duration::days(366);
+
+/// Id of a series: the md5 of its slugified original name.
+pub fn series_id(name: &str) -> String {
+    format!("{:x}", md5::compute(slugify(name)))
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Follows {
     //#[serde(borrow = "'a")]
     // key is the md5 of the slugified original Japanese name
     pub following: HashMap<String, AniInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Follows {
+    /// What is left to watch of these series, as /towatch lists it.
+    pub fn backlog(&self, updates: &Updates) -> Backlog {
+        let mut backlog = Backlog::default();
+        for (id, info) in self.following.iter() {
+            let episodes = info.unwatched(id, updates);
+            if episodes > 0 {
+                backlog.series += 1;
+                backlog.episodes += i64::from(episodes);
+                backlog.minutes += i64::from(episodes) * i64::from(info.episode_minutes());
+            }
+        }
+        backlog
+    }
+}
+
+/// Episodes released but not watched yet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Backlog {
+    pub series: usize,
+    pub episodes: i64,
+    pub minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Updates {
     pub updates: HashMap<String, AniMinInfo>,
+    // what happened to our series, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<HistoryEvent>,
+}
+
+impl Updates {
+    /// Appends a change of a series to the history, forgetting the ones too old to matter.
+    pub fn record(&mut self, id: &str, change: Change, time: DateTime<Utc>) {
+        self.history.retain(|e| time - e.time < HISTORY_KEPT);
+        self.history.push(HistoryEvent {
+            time,
+            id: id.to_owned(),
+            change,
+        });
+    }
+
+    /// Changes recorded from `from` up to, but not including, `to`.
+    pub fn history_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> impl Iterator<Item = &HistoryEvent> {
+        self.history
+            .iter()
+            .filter(move |e| from <= e.time && e.time < to)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEvent {
+    pub time: DateTime<Utc>,
+    // id of the series
+    pub id: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Change {
+    /// Episodes of a followed series were notified.
+    Released {
+        episodes: Vec<i16>,
+    },
+    /// Our progress went from one episode to another.
+    Watched {
+        from: i16,
+        to: i16,
+    },
+    Finished,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq)]
@@ -23,6 +120,29 @@ pub struct AniInfo {
     pub extra: AniExtraInfo,
 }
 
+impl AniInfo {
+    /// When our progress or the latest release of the series was last recorded.
+    pub fn last_updated(&self, id: &str, updates: &Updates) -> Option<DateTime<Utc>> {
+        let released = updates
+            .updates
+            .get(&self.extra.variant.update_key(id))
+            .and_then(|u| u.updated);
+        self.info.updated.max(released)
+    }
+
+    /// Episodes that were notified but we haven't watched yet.
+    pub fn unwatched(&self, id: &str, updates: &Updates) -> i16 {
+        updates
+            .updates
+            .get(&self.extra.variant.update_key(id))
+            .map_or(0, |u| (u.last_episode - self.info.last_episode).max(0))
+    }
+
+    pub fn episode_minutes(&self) -> u16 {
+        self.extra.duration.unwrap_or(DEFAULT_EPISODE_MINUTES)
+    }
+}
+
 impl Ord for AniInfo {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.extra.cmp(&other.extra)
@@ -45,6 +165,9 @@ impl PartialEq for AniInfo {
 pub struct AniMinInfo {
     pub name: String,
     pub last_episode: i16,
+    // when the episode was recorded, unknown for the ones recorded before we kept it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
 }
 
 impl Ord for AniMinInfo {
@@ -65,10 +188,143 @@ impl PartialEq for AniMinInfo {
     }
 }
 
+/// Releases found in the sources keyed by series id and variant.
+pub type Releases = HashMap<(String, AnimeVariant), AniRelease>;
+
+/// Release of a series found in the sources, grouping every episode seen in a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AniRelease {
+    pub name: String,
+    // sorted by episode number and without duplicates
+    pub episodes: Vec<AniEpisode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AniEpisode {
+    pub number: i16,
+    pub link: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+}
+
+impl AniEpisode {
+    pub fn new(number: i16, link: Option<String>, published: Option<DateTime<Utc>>) -> Self {
+        AniEpisode {
+            number,
+            link,
+            published,
+        }
+    }
+}
+
+impl AniRelease {
+    pub fn new(name: &str, episode: AniEpisode) -> Self {
+        AniRelease {
+            name: name.to_owned(),
+            episodes: vec![episode],
+        }
+    }
+
+    /// Adds an episode, completing the link and publish date if it was already known.
+    pub fn add_episode(&mut self, episode: AniEpisode) {
+        match self
+            .episodes
+            .binary_search_by_key(&episode.number, |e| e.number)
+        {
+            Ok(pos) => {
+                let known = &mut self.episodes[pos];
+                if known.link.is_none() {
+                    known.link = episode.link;
+                }
+                if known.published.is_none() {
+                    known.published = episode.published;
+                }
+            }
+            Err(pos) => self.episodes.insert(pos, episode),
+        }
+    }
+
+    pub fn merge(&mut self, other: AniRelease) {
+        for episode in other.episodes {
+            self.add_episode(episode);
+        }
+    }
+
+    pub fn last_episode(&self) -> i16 {
+        self.episodes.last().map(|e| e.number).unwrap_or_default()
+    }
+
+    /// episodes released after the given one
+    pub fn episodes_after(&self, episode: i16) -> Vec<&AniEpisode> {
+        self.episodes
+            .iter()
+            .filter(|e| e.number > episode)
+            .collect()
+    }
+
+    pub fn to_min_info(&self) -> AniMinInfo {
+        AniMinInfo {
+            name: self.name.to_owned(),
+            last_episode: self.last_episode(),
+            updated: None,
+        }
+    }
+}
+
+/// Formats a sorted list of episodes collapsing consecutive ones, e.g. "Ep. 2, 5–7".
+pub fn format_episodes(episodes: &[i16]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < episodes.len() {
+        let start = episodes[i];
+        let mut end = start;
+        while i + 1 < episodes.len() && episodes[i + 1] == end + 1 {
+            i += 1;
+            end = episodes[i];
+        }
+        if start == end {
+            ranges.push(format!("{start}"));
+        } else {
+            ranges.push(format!("{start}–{end}"));
+        }
+        i += 1;
+    }
+    format!("Ep. {}", ranges.join(", "))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Eq)]
 pub struct AniExtraInfo {
     pub en_name: String,
     pub season: AnimeSeason,
+    #[serde(default, skip_serializing_if = "AnimeVariant::is_sub")]
+    pub variant: AnimeVariant,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub airing: Option<Airing>,
+    // minutes per episode, DEFAULT_EPISODE_MINUTES if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u16>,
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
+    // how far each member of the chat has watched, keyed by their user id
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub members: BTreeMap<u64, MemberProgress>,
+    // from 1 to MAX_SCORE, usually given when we finish it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<u8>,
+    // whether we liked each episode that we rated
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thumbs: BTreeMap<i16, bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // our own labels, as given by normalize_tag
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+/// Last episode that a member of the chat marked as watched.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MemberProgress {
+    pub name: String,
+    pub last_episode: i16,
 }
 
 impl Default for AniExtraInfo {
@@ -76,10 +332,33 @@ impl Default for AniExtraInfo {
         AniExtraInfo {
             en_name: String::new(),
             season: AnimeSeason::Unknown,
+            variant: AnimeVariant::Sub,
+            airing: None,
+            duration: None,
+            priority: Priority::Normal,
+            members: BTreeMap::new(),
+            score: None,
+            thumbs: BTreeMap::new(),
+            note: None,
+            tags: BTreeSet::new(),
         }
     }
 }
 
+impl AniExtraInfo {
+    /// Records that a member of the chat watched up to `episode`, never going back on
+    /// what they marked before. Returns the last episode that they watched.
+    pub fn mark_watched(&mut self, user_id: u64, name: &str, episode: i16) -> i16 {
+        let progress = self.members.entry(user_id).or_insert(MemberProgress {
+            name: name.to_owned(),
+            last_episode: 0,
+        });
+        progress.name = name.to_owned();
+        progress.last_episode = progress.last_episode.max(episode);
+        progress.last_episode
+    }
+}
+
 impl Ord for AniExtraInfo {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.en_name.cmp(&other.en_name)
@@ -118,3 +397,275 @@ impl Display for AnimeSeason {
         }
     }
 }
+
+impl AnimeSeason {
+    /// Season that is airing now.
+    pub fn current() -> AnimeSeason {
+        AnimeSeason::of_date(Utc::now().date_naive())
+    }
+
+    /// Season of a date: winter starts in January, spring in April, summer in July and
+    /// autumn in October.
+    pub fn of_date(date: NaiveDate) -> AnimeSeason {
+        let year = u16::try_from(date.year()).unwrap_or_default();
+        match date.month() {
+            1..=3 => AnimeSeason::Winter(year),
+            4..=6 => AnimeSeason::Spring(year),
+            7..=9 => AnimeSeason::Summer(year),
+            _ => AnimeSeason::Autumn(year),
+        }
+    }
+
+    /// First day of the season, None if it is unknown.
+    pub fn starts(&self) -> Option<NaiveDate> {
+        let (year, quarter) = self.year_quarter()?;
+        NaiveDate::from_ymd_opt(year.into(), u32::from(quarter) * 3 - 2, 1)
+    }
+
+    /// Year and quarter of the season, None if it is unknown.
+    pub fn year_quarter(&self) -> Option<(u16, u8)> {
+        match self {
+            AnimeSeason::Winter(y) => Some((*y, 1)),
+            AnimeSeason::Spring(y) => Some((*y, 2)),
+            AnimeSeason::Summer(y) => Some((*y, 3)),
+            AnimeSeason::Autumn(y) => Some((*y, 4)),
+            AnimeSeason::Unknown => None,
+        }
+    }
+}
+
+/// Chronological, with the unknown season after every known one.
+impl Ord for AnimeSeason {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self.year_quarter(), other.year_quarter()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        }
+    }
+}
+
+impl PartialOrd for AnimeSeason {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for AnimeSeason {
+    type Err = String;
+
+    /// Parses the Display form, e.g. "Spring 2024", or "2024-spring".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("unknown") {
+            return Ok(AnimeSeason::Unknown);
+        }
+        let (name, year) = match s.split_once(' ').or(s.split_once('-')) {
+            Some((year, name)) if year.starts_with(|c: char| c.is_ascii_digit()) => (name, year),
+            Some(parts) => parts,
+            None => return Err(s.to_owned()),
+        };
+        let year = year.trim().parse::<u16>().map_err(|_| s.to_owned())?;
+        match name.to_lowercase().as_str() {
+            "winter" => Ok(AnimeSeason::Winter(year)),
+            "spring" => Ok(AnimeSeason::Spring(year)),
+            "summer" => Ok(AnimeSeason::Summer(year)),
+            "autumn" | "fall" => Ok(AnimeSeason::Autumn(year)),
+            _ => Err(s.to_owned()),
+        }
+    }
+}
+
+/// Order of the series in the lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    #[default]
+    Name,
+    // latest season first
+    Season,
+    // most watched first
+    Progress,
+    // most recently updated first
+    Updated,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 4] = [
+        SortBy::Name,
+        SortBy::Season,
+        SortBy::Progress,
+        SortBy::Updated,
+    ];
+
+    /// Comma separated list of the orders, for help messages.
+    pub fn available() -> String {
+        SortBy::ALL.map(|s| s.to_string()).join(", ")
+    }
+
+    /// Compares two series given with their ids, ties are broken by name.
+    pub fn compare(
+        &self,
+        (a_id, a): (&str, &AniInfo),
+        (b_id, b): (&str, &AniInfo),
+        updates: &Updates,
+    ) -> std::cmp::Ordering {
+        let order = match self {
+            SortBy::Name => std::cmp::Ordering::Equal,
+            SortBy::Season => {
+                let key =
+                    |s: &AnimeSeason| (*s == AnimeSeason::Unknown, std::cmp::Reverse(s.to_owned()));
+                key(&a.extra.season).cmp(&key(&b.extra.season))
+            }
+            SortBy::Progress => b.info.last_episode.cmp(&a.info.last_episode),
+            SortBy::Updated => b
+                .last_updated(b_id, updates)
+                .cmp(&a.last_updated(a_id, updates)),
+        };
+        order.then(a.cmp(b))
+    }
+}
+
+impl Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortBy::Name => write!(f, "name"),
+            SortBy::Season => write!(f, "season"),
+            SortBy::Progress => write!(f, "progress"),
+            SortBy::Updated => write!(f, "updated"),
+        }
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SortBy::ALL
+            .into_iter()
+            .find(|o| o.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or(s.to_owned())
+    }
+}
+
+/// Longest tag that we accept, in characters.
+pub const TAG_MAX_CHARS: usize = 32;
+
+/// Tag as we store it: lowercase and with single spaces, so that "Watch with  Ana" and
+/// "watch with ana" are the same. None if it is empty or longer than `TAG_MAX_CHARS`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if tag.is_empty() || tag.chars().count() > TAG_MAX_CHARS {
+        return None;
+    }
+    Some(tag.to_lowercase())
+}
+
+/// How soon we want to watch a series, the most urgent first.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    pub fn is_normal(&self) -> bool {
+        *self == Priority::Normal
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::High => write!(f, "high"),
+            Priority::Normal => write!(f, "normal"),
+            Priority::Low => write!(f, "low"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::ALL
+            .into_iter()
+            .find(|p| p.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or(s.to_owned())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AnimeVariant {
+    #[default]
+    Sub,
+    Dub,
+    Raw,
+}
+
+impl AnimeVariant {
+    pub fn is_sub(&self) -> bool {
+        *self == AnimeVariant::Sub
+    }
+
+    /// Key under which the releases of this variant are recorded in `Updates`, so
+    /// that a dub release does not clobber the sub progress of the same series.
+    pub fn update_key(&self, id: &str) -> String {
+        match self {
+            AnimeVariant::Sub => id.to_owned(),
+            AnimeVariant::Dub => format!("{id}:dub"),
+            AnimeVariant::Raw => format!("{id}:raw"),
+        }
+    }
+
+    /// Splits the variant marker off a release title, e.g. "Some name (Dub)".
+    pub fn split_title(title: &str) -> (AnimeVariant, &str) {
+        let trimmed = title.trim_end();
+        for (marker, variant) in [("(Dub)", AnimeVariant::Dub), ("(Raw)", AnimeVariant::Raw)] {
+            if let Some(name) = trimmed.strip_suffix(marker) {
+                return (variant, name.trim_end());
+            }
+        }
+        (AnimeVariant::Sub, trimmed)
+    }
+}
+
+impl Display for AnimeVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimeVariant::Sub => write!(f, "sub"),
+            AnimeVariant::Dub => write!(f, "dub"),
+            AnimeVariant::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+impl FromStr for AnimeVariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sub" => Ok(AnimeVariant::Sub),
+            "dub" => Ok(AnimeVariant::Dub),
+            "raw" => Ok(AnimeVariant::Raw),
+            _ => Err(s.to_owned()),
+        }
+    }
+}
+
+/// Values that the tests of several modules build.
+#[cfg(test)]
+pub mod fixtures {
+    use super::Follows;
+    use chrono::{DateTime, Utc};
+
+    pub fn follows(json: &str) -> Follows {
+        serde_json::from_str(json).unwrap()
+    }
+
+    pub fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use teloxide::types::{ChatId, MessageId};
+
+// Version of the callback data layout, bump it whenever the encoding changes so that
+// buttons of old messages are recognised as outdated instead of misread.
+const CALLBACK_VERSION: &str = "v1";
+// Telegram limit for the callback data of a button, in bytes
+const CALLBACK_DATA_LIMIT: usize = 64;
+const SEPARATOR: char = ':';
+// Telegram limit for the text that answers a button, in UTF-16 code units
+const ANSWER_TEXT_LIMIT: usize = 200;
+// how many handled prompts we remember, older ones have lost their keyboard long ago
+const HANDLED_PROMPTS_KEPT: usize = 256;
+
+/// Action requested by a keyboard button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallbackAction {
+    Update,
+    Finish,
+    Airing,
+    Duration,
+    Priority,
+    Watched,
+    Rate,
+    Thumb,
+    Note,
+    Tag,
+}
+
+impl CallbackAction {
+    /// Whether the buttons ask a single question, to be answered once. The others may be
+    /// tapped by several members or changed later.
+    pub fn is_prompt(&self) -> bool {
+        !matches!(
+            self,
+            CallbackAction::Watched | CallbackAction::Rate | CallbackAction::Thumb
+        )
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            CallbackAction::Update => "u",
+            CallbackAction::Finish => "f",
+            CallbackAction::Airing => "a",
+            CallbackAction::Duration => "d",
+            CallbackAction::Priority => "p",
+            CallbackAction::Watched => "w",
+            CallbackAction::Rate => "r",
+            CallbackAction::Thumb => "t",
+            CallbackAction::Note => "n",
+            CallbackAction::Tag => "g",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "u" => Some(CallbackAction::Update),
+            "f" => Some(CallbackAction::Finish),
+            "a" => Some(CallbackAction::Airing),
+            "d" => Some(CallbackAction::Duration),
+            "p" => Some(CallbackAction::Priority),
+            "w" => Some(CallbackAction::Watched),
+            "r" => Some(CallbackAction::Rate),
+            "t" => Some(CallbackAction::Thumb),
+            "n" => Some(CallbackAction::Note),
+            "g" => Some(CallbackAction::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// Data carried by a keyboard button, encoded as "v1:<action>:<id>[:<arg>]".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackData {
+    pub action: CallbackAction,
+    pub id: String,
+    pub arg: Option<String>,
+}
+
+impl CallbackData {
+    pub fn new(action: CallbackAction, id: &str) -> Self {
+        CallbackData {
+            action,
+            id: id.to_owned(),
+            arg: None,
+        }
+    }
+
+    pub fn with_arg(mut self, arg: &str) -> Self {
+        self.arg = Some(arg.to_owned());
+        self
+    }
+
+    /// Encodes the data, or returns None if it doesn't fit in a button.
+    pub fn encode(&self) -> Option<String> {
+        let mut ret = format!(
+            "{CALLBACK_VERSION}{SEPARATOR}{}{SEPARATOR}{}",
+            self.action.code(),
+            self.id
+        );
+        if let Some(arg) = &self.arg {
+            ret.push(SEPARATOR);
+            ret.push_str(arg);
+        }
+        if ret.len() > CALLBACK_DATA_LIMIT || self.id.contains(SEPARATOR) {
+            log::error!("unable to encode callback data {:?}", self);
+            return None;
+        }
+        Some(ret)
+    }
+
+    /// Parses the data of a button, None if it was produced by another version.
+    pub fn parse(data: &str) -> Option<Self> {
+        let mut parts = data.splitn(4, SEPARATOR);
+        if parts.next()? != CALLBACK_VERSION {
+            return None;
+        }
+        let action = CallbackAction::from_code(parts.next()?)?;
+        let id = parts.next().filter(|id| !id.is_empty())?;
+        Some(CallbackData {
+            action,
+            id: id.to_owned(),
+            arg: parts.next().map(str::to_owned),
+        })
+    }
+}
+
+/// Cuts the text that answers a button to what Telegram accepts, long titles would make
+/// the answer fail.
+pub fn answer_text(text: &str) -> String {
+    if text.chars().map(char::len_utf16).sum::<usize>() <= ANSWER_TEXT_LIMIT {
+        return text.to_owned();
+    }
+    let mut len = 0;
+    let mut ret: String = text
+        .chars()
+        .take_while(|c| {
+            len += c.len_utf16();
+            // room for the ellipsis
+            len < ANSWER_TEXT_LIMIT
+        })
+        .collect();
+    ret.push('…');
+    ret
+}
+
+/// Prompts whose keyboard has already been answered, so that a second tap that arrives
+/// before the keyboard is removed gets rejected.
+#[derive(Default)]
+pub struct HandledPrompts {
+    prompts: Mutex<VecDeque<(ChatId, MessageId)>>,
+}
+
+impl HandledPrompts {
+    /// Marks a prompt as handled. Returns false if it had already been handled.
+    pub fn claim(&self, chat_id: ChatId, message_id: MessageId) -> bool {
+        let mut prompts = self.prompts.lock().expect("Error locking handled prompts");
+        if prompts.contains(&(chat_id, message_id)) {
+            return false;
+        }
+        if prompts.len() == HANDLED_PROMPTS_KEPT {
+            prompts.pop_front();
+        }
+        prompts.push_back((chat_id, message_id));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callback_data_encoding() {
+        let id = "098f6bcd4621d373cade4e832627b4f6";
+        let data = CallbackData::new(CallbackAction::Finish, id);
+        let encoded = data.encode().unwrap();
+        assert_eq!(encoded, format!("v1:f:{id}"));
+        assert_eq!(CallbackData::parse(&encoded), Some(data));
+
+        let data = CallbackData::new(CallbackAction::Update, id).with_arg("12:b");
+        assert_eq!(CallbackData::parse(&data.encode().unwrap()), Some(data));
+        let data = CallbackData::new(CallbackAction::Watched, id).with_arg("1042");
+        assert_eq!(CallbackData::parse(&data.encode().unwrap()), Some(data));
+
+        // bare ids and other versions are not trusted
+        assert_eq!(CallbackData::parse(id), None);
+        assert_eq!(CallbackData::parse(&format!("v0:u:{id}")), None);
+        assert_eq!(CallbackData::parse("v1:x:abc"), None);
+        // too long for Telegram
+        let data = CallbackData::new(CallbackAction::Update, id).with_arg(&"a".repeat(30));
+        assert_eq!(data.encode(), None);
+    }
+
+    #[test]
+    fn long_answers_are_cut() {
+        assert_eq!(answer_text("Ep. 3"), "Ep. 3");
+        let text = answer_text(&"😀".repeat(150));
+        assert_eq!(text.chars().map(char::len_utf16).sum::<usize>(), 199);
+        assert!(text.ends_with("😀…"));
+    }
+
+    #[test]
+    fn prompts_are_handled_once() {
+        let handled = HandledPrompts::default();
+        assert!(handled.claim(ChatId(1), MessageId(10)));
+        assert!(!handled.claim(ChatId(1), MessageId(10)));
+        assert!(handled.claim(ChatId(2), MessageId(10)));
+    }
+}
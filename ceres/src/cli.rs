@@ -0,0 +1,405 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use teloxide::prelude::*;
+
+use crate::anime::{
+    series_id, AniExtraInfo, AniInfo, AniMinInfo, AnimeSeason, AnimeVariant, Change, Follows,
+    Priority, SortBy, Updates,
+};
+use crate::i18n::{tr, Lang};
+use crate::schedule::Airing;
+use crate::settings::Settings;
+use crate::state::State;
+use crate::storage::{self, DIALOGUES_FILE, FINISHED_FILE, FOLLOWING_FILE, UPDATES_FILE};
+use crate::{import, AnimeState, Result};
+
+/// Telegram bot that keeps track of the anime series that we follow. Runs the bot
+/// unless a subcommand is given.
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Imports a MyAnimeList XML or AniList JSON export into the storage
+    Import { file: PathBuf },
+    /// Starts following a series, given its original name as it appears in the feeds
+    Follow {
+        name: String,
+        /// English name, defaults to the original one
+        #[arg(long)]
+        en_name: Option<String>,
+        /// e.g. "Spring 2024"
+        #[arg(long, default_value = "Unknown")]
+        season: AnimeSeason,
+        #[arg(long, default_value = "sub")]
+        variant: AnimeVariant,
+        /// Last watched episode
+        #[arg(long, default_value_t = 0)]
+        episode: i16,
+    },
+    /// Stops following a series, given its id or original name
+    Unfollow { series: String },
+    /// Sets the last watched episode of a followed series
+    SetEpisode { series: String, episode: i16 },
+    /// Sets when a followed series airs every week, e.g. "sat 23:30 Asia/Tokyo" (UTC
+    /// unless a timezone is given), or "off" to forget it
+    SetAiring {
+        series: String,
+        #[arg(required = true)]
+        airing: Vec<String>,
+    },
+    /// Sets how many minutes the episodes of a followed series last, or "off" to go back
+    /// to the default
+    SetDuration { series: String, minutes: String },
+    /// Sets how soon we want to watch a followed series: high, normal or low
+    SetPriority { series: String, priority: Priority },
+    /// Lists the series that we follow
+    List {
+        /// List the finished series instead
+        #[arg(long)]
+        finished: bool,
+        /// name, season, progress or updated
+        #[arg(long, default_value = "name")]
+        sort: SortBy,
+    },
+    /// Checks the feeds for updates and notifies them to TCHAT_ID
+    Check {
+        /// Only print the releases of each source and what would be notified, without
+        /// notifying or recording anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Prints the id of a series, given its original name
+    GenId { name: String },
+    /// Checks that the storage files can be read and are consistent
+    ValidateStorage,
+}
+
+pub async fn run(command: CliCommand) -> Result<()> {
+    let state = State::new();
+    match command {
+        CliCommand::Import { file } => {
+            let entries = import::parse(&tokio::fs::read(file).await?)?;
+            let report = crate::import_entries(&state, entries).await?;
+            println!("{}", report.summary(Lang::En));
+            if !report.unmapped.is_empty() {
+                println!("{}", tr(Lang::En, "import-unmapped-header"));
+                for title in report.unmapped {
+                    println!("— {title}");
+                }
+            }
+        }
+        CliCommand::Follow {
+            name,
+            en_name,
+            season,
+            variant,
+            episode,
+        } => {
+            let id = series_id(&name);
+            let info = AniInfo {
+                info: AniMinInfo {
+                    name: name.to_owned(),
+                    last_episode: episode,
+                    updated: None,
+                },
+                extra: AniExtraInfo {
+                    en_name: en_name.unwrap_or(name.to_owned()),
+                    season,
+                    variant,
+                    ..Default::default()
+                },
+            };
+            let line = list_line(&id, &info);
+            let added = state
+                .update(|lists| {
+                    if lists.following.following.contains_key(&id)
+                        || lists.finished.following.contains_key(&id)
+                    {
+                        return false;
+                    }
+                    lists.following.following.insert(id.to_owned(), info);
+                    true
+                })
+                .await?;
+            if !added {
+                return Err(format!("{name} ({id}) is already in our lists").into());
+            }
+            println!("following {line}");
+        }
+        CliCommand::Unfollow { series } => {
+            let line = state
+                .update(|lists| -> Result<Option<String>> {
+                    let id = resolve_id(&lists.following, &series)?;
+                    let info = lists.following.following.remove(&id);
+                    Ok(info.map(|info| list_line(&id, &info)))
+                })
+                .await??;
+            if let Some(line) = line {
+                println!("unfollowed {line}");
+            }
+        }
+        CliCommand::SetEpisode { series, episode } => {
+            let line = state
+                .update(|lists| -> Result<Option<String>> {
+                    let id = resolve_id(&lists.following, &series)?;
+                    let info = lists.following.following.get_mut(&id);
+                    Ok(info.map(|info| {
+                        let now = Utc::now();
+                        let from = info.info.last_episode;
+                        info.info.last_episode = episode;
+                        info.info.updated = Some(now);
+                        let watched = Change::Watched { from, to: episode };
+                        lists.updates.record(&id, watched, now);
+                        list_line(&id, info)
+                    }))
+                })
+                .await??;
+            if let Some(line) = line {
+                println!("updated {line}");
+            }
+        }
+        CliCommand::SetAiring { series, airing } => {
+            let airing = match airing.join(" ").as_str() {
+                "off" => None,
+                airing => Some(Airing::parse(airing, Tz::UTC).map_err(|a| {
+                    format!("invalid airing \"{a}\", expected e.g. \"sat 23:30 Asia/Tokyo\"")
+                })?),
+            };
+            let line = state
+                .update(|lists| -> Result<String> {
+                    let id = resolve_id(&lists.following, &series)?;
+                    let info = lists.following.following.get_mut(&id);
+                    Ok(info.map_or(String::new(), |info| {
+                        info.extra.airing = airing;
+                        list_line(&id, info)
+                    }))
+                })
+                .await??;
+            match airing {
+                Some(airing) => println!("{line} airs on {}", airing.describe(Lang::En)),
+                None => println!("{line} has no airing time"),
+            }
+        }
+        CliCommand::SetDuration { series, minutes } => {
+            let duration = match minutes.as_str() {
+                "off" => None,
+                minutes => match minutes.parse::<u16>() {
+                    Ok(minutes) if minutes > 0 => Some(minutes),
+                    _ => {
+                        return Err(format!(
+                            "invalid duration \"{minutes}\", expected minutes or \"off\""
+                        )
+                        .into())
+                    }
+                },
+            };
+            let line = state
+                .update(|lists| -> Result<Option<(String, u16)>> {
+                    let id = resolve_id(&lists.following, &series)?;
+                    let info = lists.following.following.get_mut(&id);
+                    Ok(info.map(|info| {
+                        info.extra.duration = duration;
+                        (list_line(&id, info), info.episode_minutes())
+                    }))
+                })
+                .await??;
+            if let Some((line, minutes)) = line {
+                println!("{line} has episodes of {minutes} minutes");
+            }
+        }
+        CliCommand::SetPriority { series, priority } => {
+            let line = state
+                .update(|lists| -> Result<Option<String>> {
+                    let id = resolve_id(&lists.following, &series)?;
+                    let info = lists.following.following.get_mut(&id);
+                    Ok(info.map(|info| {
+                        info.extra.priority = priority;
+                        list_line(&id, info)
+                    }))
+                })
+                .await??;
+            if let Some(line) = line {
+                println!("{line} has {priority} priority");
+            }
+        }
+        CliCommand::List { finished, sort } => {
+            let lists = state.snapshot().await?;
+            let follows = if finished {
+                &lists.finished
+            } else {
+                &lists.following
+            };
+            let mut series: Vec<(&String, &AniInfo)> = follows.following.iter().collect();
+            series.sort_unstable_by(|a, b| sort.compare((a.0, a.1), (b.0, b.1), &lists.updates));
+            for (id, info) in series {
+                println!("{}", list_line(id, info));
+            }
+        }
+        CliCommand::Check { dry_run: false } => {
+            let chat_id = env::var("TCHAT_ID")?.parse::<i64>()?;
+            crate::check_updates(&state, ChatId(chat_id), &Bot::from_env()).await?;
+        }
+        CliCommand::Check { dry_run: true } => {
+            print!("{}", crate::check_dry_run(&state, Lang::En, false).await?);
+        }
+        CliCommand::GenId { name } => println!("{}", series_id(&name)),
+        CliCommand::ValidateStorage => {
+            let problems = validate_storage().await;
+            for problem in problems.iter() {
+                println!("{problem}");
+            }
+            if !problems.is_empty() {
+                return Err(format!("found {} problems in the storage", problems.len()).into());
+            }
+            println!("the storage looks fine");
+        }
+    }
+    Ok(())
+}
+
+/// "<id>  <en name> [<season>] (<variant>) - Ep. N"
+fn list_line(id: &str, info: &AniInfo) -> String {
+    let variant = if info.extra.variant.is_sub() {
+        String::new()
+    } else {
+        format!(" ({})", info.extra.variant)
+    };
+    format!(
+        "{id}  {} [{}]{variant} - Ep. {}",
+        info.extra.en_name, info.extra.season, info.info.last_episode
+    )
+}
+
+/// Finds a followed series by id or by its original name.
+fn resolve_id(following: &Follows, series: &str) -> Result<String> {
+    if following.following.contains_key(series) {
+        return Ok(series.to_owned());
+    }
+    let id = series_id(series);
+    if following.following.contains_key(&id) {
+        return Ok(id);
+    }
+    Err(format!("we are not following {series}").into())
+}
+
+async fn validate_storage() -> Vec<String> {
+    let mut problems = Vec::new();
+    let following = storage::load::<Follows>(FOLLOWING_FILE).await;
+    let finished = storage::load::<Follows>(FINISHED_FILE).await;
+    let updates = storage::load::<Updates>(UPDATES_FILE).await;
+    for (file, error) in [
+        (FOLLOWING_FILE, following.as_ref().err()),
+        (FINISHED_FILE, finished.as_ref().err()),
+        (UPDATES_FILE, updates.as_ref().err()),
+    ] {
+        if let Some(e) = error {
+            problems.push(format!("{file}: {e}"));
+        }
+    }
+    // these two are created by the bot when needed
+    for file in [DIALOGUES_FILE, crate::settings::SETTINGS_FILE] {
+        if !storage::path(file).exists() {
+            continue;
+        }
+        let error = if file == DIALOGUES_FILE {
+            storage::load::<HashMap<i64, AnimeState>>(file).await.err()
+        } else {
+            storage::load::<Settings>(file).await.err()
+        };
+        if let Some(e) = error {
+            problems.push(format!("{file}: {e}"));
+        }
+    }
+    if let (Ok(following), Ok(finished)) = (following, finished) {
+        problems.extend(check_follows(&following, &finished));
+    }
+    problems
+}
+
+/// Looks for inconsistencies between the following and finished lists.
+fn check_follows(following: &Follows, finished: &Follows) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (file, follows) in [(FOLLOWING_FILE, following), (FINISHED_FILE, finished)] {
+        let mut ids: Vec<&String> = follows.following.keys().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let info = &follows.following[id];
+            if *id != series_id(&info.info.name) {
+                problems.push(format!(
+                    "{file}: {id} doesn't match the id of its name \"{}\"",
+                    info.info.name
+                ));
+            }
+            if info.info.last_episode < 0 {
+                problems.push(format!("{file}: {id} has a negative episode"));
+            }
+            if file == FOLLOWING_FILE && finished.following.contains_key(id) {
+                problems.push(format!("{id} is both followed and finished"));
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_subcommands() {
+        let cli = Cli::try_parse_from([
+            "ceres",
+            "follow",
+            "Sousou no Frieren",
+            "--season",
+            "Autumn 2023",
+            "--variant",
+            "dub",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(CliCommand::Follow {
+                season, variant, ..
+            }) => {
+                assert_eq!(season, AnimeSeason::Autumn(2023));
+                assert_eq!(variant, AnimeVariant::Dub);
+            }
+            _ => unreachable!(),
+        }
+        assert!(matches!(
+            Cli::try_parse_from(["ceres", "check", "--dry-run"]).map(|c| c.command),
+            Ok(Some(CliCommand::Check { dry_run: true }))
+        ));
+        assert!(Cli::try_parse_from(["ceres", "set-episode", "abc", "x"]).is_err());
+        assert!(Cli::try_parse_from(["ceres"]).unwrap().command.is_none());
+    }
+
+    #[test]
+    fn storage_inconsistencies() {
+        let info: AniInfo = serde_json::from_str(
+            r#"{"info": {"name": "Monster", "last_episode": 74},
+                "extra": {"en_name": "Monster", "season": "Unknown"}}"#,
+        )
+        .unwrap();
+        let id = series_id("Monster");
+        let following = Follows {
+            following: HashMap::from([(id.to_owned(), info.to_owned())]),
+        };
+        let finished = Follows {
+            following: HashMap::from([(id.to_owned(), info.to_owned()), ("abc".to_owned(), info)]),
+        };
+        let problems = check_follows(&following, &finished);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("both followed and finished"));
+        assert!(problems[1].contains("abc doesn't match"));
+    }
+}
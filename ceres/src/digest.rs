@@ -0,0 +1,291 @@
+use chrono::{
+    DateTime, Datelike, Duration, DurationRound, NaiveDate, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::sync::Arc;
+use teloxide::prelude::*;
+
+use crate::anime::{format_episodes, AnimeSeason, Change};
+use crate::i18n::{tr, tr_args, Lang};
+use crate::settings::{self, ChatSettings};
+use crate::state::{Lists, State};
+use crate::{render, Result};
+
+/// Hour of the day, in the timezone of the chat, at which the weekly digest is sent.
+pub const DIGEST_HOUR: u32 = 10;
+
+/// Sends the digests that are due, waking up at every o'clock. A digest missed while the
+/// bot was down is sent on the first wake up after it. Never returns.
+pub async fn run(bot: Bot, state: Arc<State>) {
+    loop {
+        let now = Utc::now();
+        let hour = now.duration_trunc(Duration::hours(1)).unwrap_or(now) + Duration::hours(1);
+        tokio::time::sleep((hour - now).to_std().unwrap_or_default()).await;
+        // the o'clock that we waited for, the timer may wake up a bit early
+        if let Err(e) = send_due(&bot, &state, hour).await {
+            log::error!("unable to send the digests: {}", e);
+        }
+    }
+}
+
+async fn send_due(bot: &Bot, state: &State, now: DateTime<Utc>) -> Result<()> {
+    for (chat_id, chat) in settings::load().await?.chats {
+        let scheduled = match due(&chat, now) {
+            Some(scheduled) => scheduled,
+            None => continue,
+        };
+        send(bot, state, ChatId(chat_id), &chat, scheduled).await?;
+        // the chat may have changed its settings meanwhile, enabling the digest again
+        // records a later time that we keep
+        settings::update(|settings| {
+            let chat = settings.chat_mut(ChatId(chat_id));
+            chat.last_digest = chat.last_digest.max(Some(scheduled));
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Scheduled time of the weekly digest of a chat if it hasn't been sent yet.
+fn due(chat: &ChatSettings, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let scheduled = last_scheduled(chat.digest?, chat.timezone(), now)?;
+    match chat.last_digest {
+        Some(last) if last >= scheduled => None,
+        _ => Some(scheduled),
+    }
+}
+
+// latest time, up to now, at which the digest of the given day is scheduled
+fn last_scheduled(day: Weekday, timezone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let today = now.with_timezone(&timezone).date_naive();
+    let hour = NaiveTime::from_hms_opt(DIGEST_HOUR, 0, 0)?;
+    (0..=7)
+        .map(|back| today - Duration::days(back))
+        .filter(|date| date.weekday() == day)
+        .map(|date| local_time(date, hour, timezone))
+        .find(|scheduled| *scheduled <= now)
+}
+
+/// Sends the digest of the week up to `at`, followed by the one of the season that ended
+/// if this is the first digest of a new season.
+pub async fn send(
+    bot: &Bot,
+    state: &State,
+    chat_id: ChatId,
+    chat: &ChatSettings,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let lang = chat.language;
+    let lists = state.snapshot().await?;
+    let header = tr(lang, "digest-weekly-header");
+    let weekly = summary(lang, &header, &lists, at - Duration::weeks(1), at);
+    render::send_html(bot, chat_id, &weekly).await?;
+    if let Some((season, from, to)) = ended_season(at, chat.timezone()) {
+        let header = tr_args(
+            lang,
+            "digest-season-header",
+            &[("season", render::season(&season.to_string()).into())],
+        );
+        render::send_html(bot, chat_id, &summary(lang, &header, &lists, from, to)).await?;
+    }
+    Ok(())
+}
+
+/// The season that ended during the week up to `at`, along with when it started and ended.
+fn ended_season(
+    at: DateTime<Utc>,
+    timezone: Tz,
+) -> Option<(AnimeSeason, DateTime<Utc>, DateTime<Utc>)> {
+    let today = at.with_timezone(&timezone).date_naive();
+    let current = AnimeSeason::of_date(today);
+    let previous = AnimeSeason::of_date(today - Duration::weeks(1));
+    if current == previous {
+        return None;
+    }
+    let from = local_time(previous.starts()?, NaiveTime::MIN, timezone);
+    let to = local_time(current.starts()?, NaiveTime::MIN, timezone);
+    Some((previous, from, to))
+}
+
+fn local_time(date: NaiveDate, time: NaiveTime, timezone: Tz) -> DateTime<Utc> {
+    let local = date.and_time(time);
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .map_or_else(|| Utc.from_utc_datetime(&local), |t| t.with_timezone(&Utc))
+}
+
+/// Renders what happened to our series from `from` up to `to` and how many episodes are
+/// left to watch.
+pub fn summary(
+    lang: Lang,
+    header: &str,
+    lists: &Lists,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> String {
+    let mut released: HashMap<&str, Vec<i16>> = HashMap::new();
+    let mut watched: HashMap<&str, Vec<i16>> = HashMap::new();
+    let mut finished: Vec<&str> = Vec::new();
+    for event in lists.updates.history_between(from, to) {
+        match &event.change {
+            Change::Released { episodes } => released
+                .entry(&event.id)
+                .or_default()
+                .extend(episodes.iter()),
+            // going back on our progress isn't watching anything
+            Change::Watched { from, to } if from < to => {
+                watched.entry(&event.id).or_default().extend(from + 1..=*to)
+            }
+            Change::Watched { .. } => {}
+            Change::Finished => finished.push(&event.id),
+        }
+    }
+    let mut ret = format!("{header}\n");
+    let quiet = released.is_empty() && watched.is_empty() && finished.is_empty();
+    for (key, episodes) in [("digest-released", released), ("digest-watched", watched)] {
+        let total: usize = episodes.values().map(Vec::len).sum();
+        if total == 0 {
+            continue;
+        }
+        ret.push_str(&format!(
+            "\n{}\n",
+            tr_args(lang, key, &[("episodes", total.into())])
+        ));
+        let mut lines: Vec<String> = episodes
+            .into_iter()
+            .filter(|(_, numbers)| !numbers.is_empty())
+            .map(|(id, mut numbers)| {
+                numbers.sort_unstable();
+                numbers.dedup();
+                format!("— {}: {}", title(lists, id), format_episodes(&numbers))
+            })
+            .collect();
+        lines.sort_unstable();
+        ret.push_str(&(lines.join("\n") + "\n"));
+    }
+    if !finished.is_empty() {
+        ret.push_str(&format!("\n{}\n", tr(lang, "digest-finished")));
+        for id in finished {
+            ret.push_str(&format!("— {}\n", title(lists, id)));
+        }
+    }
+    if quiet {
+        ret.push_str(&format!("\n{}\n", tr(lang, "digest-quiet")));
+    }
+    ret.push('\n');
+    ret.push_str(&backlog(lang, lists));
+    ret
+}
+
+fn backlog(lang: Lang, lists: &Lists) -> String {
+    let backlog = lists.following.backlog(&lists.updates);
+    if backlog.series == 0 {
+        return tr(lang, "digest-backlog-empty");
+    }
+    tr_args(
+        lang,
+        "digest-backlog",
+        &[
+            ("episodes", backlog.episodes.into()),
+            ("series", backlog.series.into()),
+        ],
+    )
+}
+
+// english title of the series, or its id if we don't have it anymore
+fn title(lists: &Lists, id: &str) -> String {
+    let info = lists
+        .following
+        .following
+        .get(id)
+        .or_else(|| lists.finished.following.get(id));
+    match info {
+        Some(info) => render::title(&info.extra.en_name),
+        None => render::code(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::fixtures::{at, follows};
+    use crate::anime::Updates;
+
+    #[test]
+    fn weekly_summary() {
+        let mut updates: Updates =
+            serde_json::from_str(r#"{"updates": {"a": {"name": "A", "last_episode": 6}}}"#)
+                .unwrap();
+        let released = Change::Released {
+            episodes: vec![5, 6],
+        };
+        updates.record("a", released, at("2024-04-01T10:00:00Z"));
+        let watched = Change::Watched { from: 2, to: 4 };
+        updates.record("a", watched, at("2024-04-02T10:00:00Z"));
+        updates.record("b", Change::Finished, at("2024-04-03T10:00:00Z"));
+        // from the week before
+        let watched = Change::Watched { from: 1, to: 2 };
+        updates.record("a", watched, at("2024-03-25T10:00:00Z"));
+        updates.history.sort_unstable_by_key(|e| e.time);
+        let lists = Lists {
+            following: follows(
+                r#"{"following": {"a": {"info": {"name": "A", "last_episode": 4},
+                    "extra": {"en_name": "Alpha", "season": "Unknown"}}}}"#,
+            ),
+            finished: follows(
+                r#"{"following": {"b": {"info": {"name": "B", "last_episode": 12},
+                    "extra": {"en_name": "Beta", "season": "Unknown"}}}}"#,
+            ),
+            updates,
+        };
+        let now = at("2024-04-07T10:00:00Z");
+        let text = summary(Lang::En, "Week:", &lists, now - Duration::weeks(1), now);
+        assert_eq!(
+            text,
+            "Week:\n\
+             \n2 new episodes of the series that we follow:\n— <b>Alpha</b>: Ep. 5–6\n\
+             \nWe watched 2 episodes:\n— <b>Alpha</b>: Ep. 3–4\n\
+             \nWe finished:\n— <b>Beta</b>\n\
+             \n2 episodes of 1 series are waiting to be watched."
+        );
+        let text = summary(Lang::En, "Week:", &lists, now, now + Duration::weeks(1));
+        assert!(text.contains("Nothing was released nor watched."));
+    }
+
+    #[test]
+    fn missed_digests_are_due() {
+        let mut chat = ChatSettings {
+            timezone: Some(Tz::Europe__Madrid),
+            digest: Some(Weekday::Sun),
+            last_digest: Some(at("2024-04-07T08:00:00Z")),
+            ..Default::default()
+        };
+        // 10:00 in Madrid is 08:00 UTC
+        assert_eq!(due(&chat, at("2024-04-13T20:00:00Z")), None);
+        assert_eq!(
+            due(&chat, at("2024-04-14T08:00:00Z")),
+            Some(at("2024-04-14T08:00:00Z"))
+        );
+        // the bot was down at 10:00 and came back on Tuesday
+        assert_eq!(
+            due(&chat, at("2024-04-16T09:00:00Z")),
+            Some(at("2024-04-14T08:00:00Z"))
+        );
+        chat.last_digest = Some(at("2024-04-14T08:00:00Z"));
+        assert_eq!(due(&chat, at("2024-04-16T09:00:00Z")), None);
+        chat.digest = None;
+        assert_eq!(due(&chat, at("2024-04-21T08:00:00Z")), None);
+    }
+
+    #[test]
+    fn season_ends() {
+        let tz = Tz::Europe__Madrid;
+        assert!(ended_season(at("2024-04-14T08:00:00Z"), tz).is_none());
+        let (season, from, to) = ended_season(at("2024-04-07T08:00:00Z"), tz).unwrap();
+        assert_eq!(season, AnimeSeason::Winter(2024));
+        assert_eq!(from, at("2023-12-31T23:00:00Z"));
+        assert_eq!(to, at("2024-03-31T22:00:00Z"));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::anime::{format_episodes, AniRelease, AnimeVariant, Follows, Releases, Updates};
+use crate::i18n::{tr, tr_args, Lang};
+use crate::{match_release, render, PendingUpdates, ReleaseMatch};
+
+/// Renders what a check would notify, along with every release of each source and how
+/// it matched our follows. `html` chooses between a Telegram message and plain text.
+pub fn report(
+    lang: Lang,
+    sources: &[(&str, Releases)],
+    following: &Follows,
+    updates: &Updates,
+    pending: &PendingUpdates,
+    html: bool,
+) -> String {
+    let title = |name: &str| {
+        if html {
+            render::title(name)
+        } else {
+            name.to_owned()
+        }
+    };
+    let text = |text: &str| {
+        if html {
+            render::escape(text)
+        } else {
+            text.to_owned()
+        }
+    };
+    let mut ret = tr(lang, "dryrun-header") + "\n";
+    for (source, releases) in sources {
+        ret.push_str(&format!(
+            "\n{}\n",
+            tr_args(
+                lang,
+                "dryrun-source",
+                &[
+                    ("source", (*source).into()),
+                    ("count", releases.len().into())
+                ],
+            )
+        ));
+        let mut releases: Vec<(&(String, AnimeVariant), &AniRelease)> = releases.iter().collect();
+        releases
+            .sort_unstable_by_key(|((_, variant), release)| (&release.name, variant.to_string()));
+        for ((id, variant), release) in releases {
+            let numbers: Vec<i16> = release.episodes.iter().map(|e| e.number).collect();
+            let outcome = match match_release(id, *variant, release, following, updates) {
+                ReleaseMatch::NotFollowed { premiere: false } => tr(lang, "dryrun-not-followed"),
+                ReleaseMatch::NotFollowed { premiere: true } => tr(lang, "dryrun-premiere"),
+                ReleaseMatch::OtherVariant(followed) => tr_args(
+                    lang,
+                    "dryrun-other-variant",
+                    &[("variant", followed.to_string().into())],
+                ),
+                ReleaseMatch::UpToDate(known) => {
+                    tr_args(lang, "dryrun-up-to-date", &[("episode", known.into())])
+                }
+                ReleaseMatch::New(followed, episodes) => {
+                    let numbers: Vec<i16> = episodes.iter().map(|e| e.number).collect();
+                    tr_args(
+                        lang,
+                        "dryrun-new",
+                        &[
+                            ("episodes", format_episodes(&numbers).into()),
+                            ("title", title(&followed.extra.en_name).into()),
+                        ],
+                    )
+                }
+            };
+            ret.push_str(&format!(
+                "· {} ({variant}) {} → {outcome}\n",
+                text(&release.name),
+                format_episodes(&numbers)
+            ));
+        }
+    }
+    ret.push('\n');
+    if pending.is_empty() {
+        ret.push_str(&(tr(lang, "no-updates") + "\n"));
+        return ret;
+    }
+    ret.push_str(&(tr(lang, "dryrun-notify-header") + "\n"));
+    let mut episodes: Vec<_> = pending.episodes.iter().collect();
+    episodes.sort_unstable_by_key(|(en_name, _)| *en_name);
+    for (en_name, (release, new_episodes)) in episodes {
+        let numbers: Vec<i16> = new_episodes.iter().map(|e| e.number).collect();
+        let line = tr_args(
+            lang,
+            "update-line",
+            &[
+                ("episodes", format_episodes(&numbers).into()),
+                ("title", title(en_name).into()),
+                ("name", text(&release.name).into()),
+            ],
+        );
+        ret.push_str(&(line + "\n"));
+    }
+    if !pending.new_series.is_empty() {
+        ret.push_str(&(tr(lang, "new-series-header") + "\n"));
+        for series in pending.new_series.iter() {
+            ret.push_str(&format!("— {}\n", title(series)));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::AniEpisode;
+    use crate::pending_updates;
+    use std::collections::HashMap;
+
+    #[test]
+    fn explains_each_release() {
+        let following: Follows = serde_json::from_str(
+            r#"{"following": {"a": {"info": {"name": "Kimi & Boku", "last_episode": 3},
+                "extra": {"en_name": "You & Me", "season": {"Spring": 2024}}}}}"#,
+        )
+        .unwrap();
+        let updates = Updates {
+            updates: HashMap::new(),
+            history: Vec::new(),
+        };
+        let mut releases: Releases = HashMap::new();
+        let release =
+            |name: &str, number| AniRelease::new(name, AniEpisode::new(number, None, None));
+        releases.insert(
+            ("a".to_owned(), AnimeVariant::Sub),
+            release("Kimi & Boku", 4),
+        );
+        releases.insert(
+            ("a".to_owned(), AnimeVariant::Dub),
+            release("Kimi & Boku", 2),
+        );
+        releases.insert(("b".to_owned(), AnimeVariant::Sub), release("Other", 1));
+        let sources = vec![
+            ("rss sub", releases.to_owned()),
+            ("scraper", HashMap::new()),
+        ];
+        let pending = pending_updates(&releases, &following, &updates);
+        let text = report(Lang::En, &sources, &following, &updates, &pending, false);
+        assert!(text.contains("rss sub: 3 releases"));
+        assert!(text.contains("· Kimi & Boku (sub) Ep. 4 → new Ep. 4 for You & Me"));
+        assert!(text.contains("· Kimi & Boku (dub) Ep. 2 → we follow the sub release"));
+        assert!(text.contains("· Other (sub) Ep. 1 → not followed, new series"));
+        assert!(text.contains("scraper: 0 releases"));
+        let html = report(Lang::En, &sources, &following, &updates, &pending, true);
+        assert!(html.contains("<b>You &amp; Me</b>"));
+    }
+}
@@ -0,0 +1,210 @@
+use quick_xml::escape::escape;
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::anime::{AniInfo, Follows};
+use crate::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    // MyAnimeList compatible XML
+    Mal,
+    Csv,
+    // our own storage format
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Mal, ExportFormat::Csv, ExportFormat::Json];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Mal => "xml",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    /// "xml, csv, json"
+    pub fn available() -> String {
+        ExportFormat::ALL
+            .iter()
+            .map(|f| f.extension())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "xml" | "mal" => Ok(ExportFormat::Mal),
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(s),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    following: &'a Follows,
+    finished: &'a Follows,
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    id: &'a str,
+    list: &'a str,
+    name: &'a str,
+    en_name: &'a str,
+    season: String,
+    variant: String,
+    last_episode: i16,
+}
+
+/// Serializes the following and finished lists in the given format.
+pub fn export(format: ExportFormat, following: &Follows, finished: &Follows) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(&JsonExport {
+            following,
+            finished,
+        })?),
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for (list, id, info) in entries(following, finished) {
+                writer.serialize(CsvRow {
+                    id,
+                    list,
+                    name: &info.info.name,
+                    en_name: &info.extra.en_name,
+                    season: info.extra.season.to_string(),
+                    variant: info.extra.variant.to_string(),
+                    last_episode: info.info.last_episode,
+                })?;
+            }
+            Ok(writer.into_inner()?)
+        }
+        ExportFormat::Mal => Ok(mal_xml(following, finished).into_bytes()),
+    }
+}
+
+/// Entries of both lists as (list, id, info), sorted by list and English name.
+fn entries<'a>(
+    following: &'a Follows,
+    finished: &'a Follows,
+) -> Vec<(&'static str, &'a str, &'a AniInfo)> {
+    let mut ret = Vec::new();
+    for (list, follows) in [("following", following), ("finished", finished)] {
+        let mut infos: Vec<(&'static str, &str, &AniInfo)> = follows
+            .following
+            .iter()
+            .map(|(id, info)| (list, id.as_str(), info))
+            .collect();
+        infos.sort_unstable_by(|a, b| a.2.cmp(b.2));
+        ret.extend(infos);
+    }
+    ret
+}
+
+/// Renders the lists like the export of MyAnimeList. We don't know the MAL ids, so the
+/// entries are matched by title when importing them there.
+fn mal_xml(following: &Follows, finished: &Follows) -> String {
+    let mut ret = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n<myanimelist>\n");
+    ret.push_str("  <myinfo>\n    <user_export_type>1</user_export_type>\n  </myinfo>\n");
+    for (list, _, info) in entries(following, finished) {
+        let status = match list {
+            "finished" => "Completed",
+            _ => "Watching",
+        };
+        ret.push_str("  <anime>\n");
+        ret.push_str(&format!(
+            "    <series_title>{}</series_title>\n",
+            escape(&info.info.name)
+        ));
+        ret.push_str(&format!(
+            "    <my_watched_episodes>{}</my_watched_episodes>\n",
+            info.info.last_episode
+        ));
+        // 0 is how MAL says that there is no score
+        ret.push_str(&format!(
+            "    <my_score>{}</my_score>\n",
+            info.extra.score.unwrap_or_default()
+        ));
+        ret.push_str(&format!("    <my_status>{status}</my_status>\n"));
+        if !info.extra.tags.is_empty() {
+            let tags: Vec<&str> = info.extra.tags.iter().map(String::as_str).collect();
+            ret.push_str(&format!(
+                "    <my_tags>{}</my_tags>\n",
+                escape(&tags.join(", "))
+            ));
+        }
+        if let Some(note) = &info.extra.note {
+            ret.push_str(&format!(
+                "    <my_comments>{}</my_comments>\n",
+                escape(note)
+            ));
+        }
+        ret.push_str("    <update_on_import>1</update_on_import>\n");
+        ret.push_str("  </anime>\n");
+    }
+    ret.push_str("</myanimelist>\n");
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{self, ImportTarget};
+
+    fn lists() -> (Follows, Follows) {
+        let following: Follows = serde_json::from_str(
+            r#"{"following": {"a": {"info": {"name": "Kimi & Boku", "last_episode": 3},
+                "extra": {"en_name": "You & Me", "season": {"Spring": 2024}}}}}"#,
+        )
+        .unwrap();
+        let finished: Follows = serde_json::from_str(
+            r#"{"following": {"b": {"info": {"name": "Monster", "last_episode": 74},
+                "extra": {"en_name": "Monster", "season": "Unknown", "variant": "Dub"}}}}"#,
+        )
+        .unwrap();
+        (following, finished)
+    }
+
+    #[test]
+    fn mal_export_can_be_imported() -> Result<()> {
+        let (following, finished) = lists();
+        let xml = export(ExportFormat::Mal, &following, &finished)?;
+        let entries = import::parse(&xml)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Kimi & Boku");
+        assert_eq!(entries[0].target, Some(ImportTarget::Following));
+        assert_eq!(entries[1].watched, 74);
+        assert_eq!(entries[1].target, Some(ImportTarget::Finished));
+        Ok(())
+    }
+
+    #[test]
+    fn csv_and_json_exports() -> Result<()> {
+        let (following, finished) = lists();
+        let csv = String::from_utf8(export(ExportFormat::Csv, &following, &finished)?)?;
+        assert_eq!(
+            csv,
+            "id,list,name,en_name,season,variant,last_episode\n\
+             a,following,Kimi & Boku,You & Me,Spring 2024,sub,3\n\
+             b,finished,Monster,Monster,Unknown,dub,74\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_slice(&export(ExportFormat::Json, &following, &finished)?)?;
+        assert_eq!(
+            json["finished"]["following"]["b"]["info"]["last_episode"],
+            74
+        );
+        assert_eq!("MAL".parse::<ExportFormat>(), Ok(ExportFormat::Mal));
+        assert!("yaml".parse::<ExportFormat>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+    Eu,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 3] = [Lang::En, Lang::Es, Lang::Eu];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Es => "es",
+            Lang::Eu => "eu",
+        }
+    }
+
+    fn catalog(&self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.ftl"),
+            Lang::Es => include_str!("../locales/es.ftl"),
+            Lang::Eu => include_str!("../locales/eu.ftl"),
+        }
+    }
+
+    /// "en, es, eu"
+    pub fn available() -> String {
+        Lang::ALL
+            .iter()
+            .map(|l| l.code())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lang::En => write!(f, "English"),
+            Lang::Es => write!(f, "Español"),
+            Lang::Eu => write!(f, "Euskara"),
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Lang::ALL
+            .into_iter()
+            .find(|l| l.code() == s || l.to_string().to_lowercase() == s)
+            .ok_or(s)
+    }
+}
+
+static BUNDLES: OnceLock<HashMap<Lang, FluentBundle<FluentResource>>> = OnceLock::new();
+
+fn bundles() -> &'static HashMap<Lang, FluentBundle<FluentResource>> {
+    BUNDLES.get_or_init(|| {
+        let mut bundles = HashMap::new();
+        for lang in Lang::ALL {
+            let langid: LanguageIdentifier = lang.code().parse().expect("Error parsing language");
+            let resource = FluentResource::try_new(lang.catalog().to_owned())
+                .expect("Error parsing message catalog");
+            let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+            // we don't mix text directions and the isolation marks confuse Telegram clients
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .expect("Error loading message catalog");
+            bundles.insert(lang, bundle);
+        }
+        bundles
+    })
+}
+
+/// Looks up a message of the catalog.
+pub fn tr(lang: Lang, key: &str) -> String {
+    tr_args(lang, key, &[])
+}
+
+/// Looks up a message of the catalog filling in its arguments. Messages missing from a
+/// catalog fall back to English.
+pub fn tr_args(lang: Lang, key: &str, args: &[(&str, FluentValue)]) -> String {
+    let mut fargs = FluentArgs::new();
+    for (name, value) in args {
+        fargs.set(*name, value.to_owned());
+    }
+    for lang in [lang, Lang::En] {
+        let bundle = &bundles()[&lang];
+        if let Some(pattern) = bundle.get_message(key).and_then(|m| m.value()) {
+            let mut errors = vec![];
+            let ret = bundle.format_pattern(pattern, Some(&fargs), &mut errors);
+            if !errors.is_empty() {
+                log::error!(
+                    "errors formatting '{}' ({}): {:?}",
+                    key,
+                    lang.code(),
+                    errors
+                );
+            }
+            return ret.into_owned();
+        }
+        log::error!("'{}' is missing from the {} catalog", key, lang.code());
+    }
+    key.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogs_are_complete() {
+        let keys: Vec<&str> = Lang::En
+            .catalog()
+            .lines()
+            .filter_map(|l| l.split_once(" = ").map(|(key, _)| key))
+            .collect();
+        assert!(!keys.is_empty());
+        for lang in Lang::ALL {
+            for key in keys.iter() {
+                assert!(
+                    bundles()[&lang].has_message(key),
+                    "'{key}' is missing from {}",
+                    lang.code()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn message_arguments() {
+        let text = tr_args(
+            Lang::Es,
+            "updated-episode",
+            &[("title", "Some name".into()), ("episode", 7.into())],
+        );
+        assert_eq!(text, "Some name actualizado al episodio 7");
+        assert_eq!("EU".parse::<Lang>(), Ok(Lang::Eu));
+        assert!("fr".parse::<Lang>().is_err());
+    }
+}
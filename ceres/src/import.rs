@@ -0,0 +1,313 @@
+use serde::Deserialize;
+use slug::slugify;
+use std::collections::HashSet;
+
+use crate::anime::{
+    series_id, AniExtraInfo, AniInfo, AniMinInfo, AnimeSeason, AnimeVariant, Follows,
+};
+use crate::i18n::{tr_args, Lang};
+use crate::Result;
+
+/// List that an imported entry goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportTarget {
+    Following,
+    Finished,
+}
+
+/// A series read from an export file of another tracker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportEntry {
+    // main (romaji) title, the one used by the feeds
+    pub title: String,
+    pub en_title: Option<String>,
+    pub watched: i16,
+    pub season: AnimeSeason,
+    // None for the statuses that we don't track, like "plan to watch" or "dropped"
+    pub target: Option<ImportTarget>,
+}
+
+impl ImportEntry {
+    /// Id of the series in the feeds, derived from its title like /genid does.
+    pub fn id(&self) -> Option<String> {
+        if slugify(&self.title).is_empty() {
+            return None;
+        }
+        Some(series_id(&self.title))
+    }
+
+    fn to_info(&self) -> AniInfo {
+        AniInfo {
+            info: AniMinInfo {
+                name: self.title.to_owned(),
+                last_episode: self.watched,
+                updated: None,
+            },
+            extra: AniExtraInfo {
+                en_name: self.en_title.to_owned().unwrap_or(self.title.to_owned()),
+                season: self.season.to_owned(),
+                variant: AnimeVariant::Sub,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Outcome of an import.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub following: usize,
+    pub finished: usize,
+    // already in our lists, left untouched
+    pub existing: usize,
+    // with a status that we don't track
+    pub skipped: usize,
+    // titles that we couldn't match with a series of the feeds
+    pub unmapped: Vec<String>,
+}
+
+impl ImportReport {
+    /// Localized one line summary of the counts.
+    pub fn summary(&self, lang: Lang) -> String {
+        tr_args(
+            lang,
+            "import-summary",
+            &[
+                ("following", self.following.into()),
+                ("finished", self.finished.into()),
+                ("existing", self.existing.into()),
+                ("skipped", self.skipped.into()),
+            ],
+        )
+    }
+}
+
+/// Parses a MyAnimeList XML or AniList JSON export, telling them apart by their content.
+pub fn parse(content: &[u8]) -> Result<Vec<ImportEntry>> {
+    let text = std::str::from_utf8(content)?.trim_start_matches('\u{feff}');
+    match text.trim_start().chars().next() {
+        Some('<') => parse_mal(text),
+        Some('{') => parse_anilist(text),
+        _ => Err("unknown export format, expected MyAnimeList XML or AniList JSON".into()),
+    }
+}
+
+#[derive(Deserialize)]
+struct MalExport {
+    #[serde(default)]
+    anime: Vec<MalAnime>,
+}
+
+#[derive(Deserialize)]
+struct MalAnime {
+    series_title: String,
+    #[serde(default)]
+    my_watched_episodes: i16,
+    my_status: String,
+}
+
+fn parse_mal(text: &str) -> Result<Vec<ImportEntry>> {
+    let export: MalExport = quick_xml::de::from_str(text)?;
+    Ok(export
+        .anime
+        .into_iter()
+        .map(|a| ImportEntry {
+            target: match a.my_status.as_str() {
+                "Watching" | "On-Hold" => Some(ImportTarget::Following),
+                "Completed" => Some(ImportTarget::Finished),
+                _ => None,
+            },
+            title: a.series_title,
+            en_title: None,
+            watched: a.my_watched_episodes,
+            season: AnimeSeason::Unknown,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct AniListCollection {
+    lists: Vec<AniListList>,
+}
+
+#[derive(Deserialize)]
+struct AniListList {
+    entries: Vec<AniListEntry>,
+}
+
+#[derive(Deserialize)]
+struct AniListEntry {
+    status: String,
+    #[serde(default)]
+    progress: Option<i16>,
+    media: AniListMedia,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AniListMedia {
+    title: AniListTitle,
+    season: Option<String>,
+    season_year: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct AniListTitle {
+    romaji: String,
+    english: Option<String>,
+}
+
+/// Reads the `MediaListCollection` of the AniList API, either as the raw response or
+/// just the collection object.
+fn parse_anilist(text: &str) -> Result<Vec<ImportEntry>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let collection = value
+        .pointer("/data/MediaListCollection")
+        .or(value.get("MediaListCollection"))
+        .unwrap_or(&value);
+    let collection = AniListCollection::deserialize(collection)?;
+    Ok(collection
+        .lists
+        .into_iter()
+        .flat_map(|l| l.entries)
+        .map(|e| ImportEntry {
+            target: match e.status.as_str() {
+                "CURRENT" | "PAUSED" | "REPEATING" => Some(ImportTarget::Following),
+                "COMPLETED" => Some(ImportTarget::Finished),
+                _ => None,
+            },
+            season: match (e.media.season.as_deref(), e.media.season_year) {
+                (Some("WINTER"), Some(y)) => AnimeSeason::Winter(y),
+                (Some("SPRING"), Some(y)) => AnimeSeason::Spring(y),
+                (Some("SUMMER"), Some(y)) => AnimeSeason::Summer(y),
+                (Some("FALL"), Some(y)) => AnimeSeason::Autumn(y),
+                _ => AnimeSeason::Unknown,
+            },
+            title: e.media.title.romaji,
+            en_title: e.media.title.english,
+            watched: e.progress.unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Adds the entries to our lists. Series that we already have are left untouched, and
+/// the ones to follow must be known to the feeds (`feed_ids`), otherwise we would never
+/// get their updates.
+pub fn apply(
+    entries: Vec<ImportEntry>,
+    following: &mut Follows,
+    finished: &mut Follows,
+    feed_ids: &HashSet<String>,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    for entry in entries {
+        let target = match entry.target {
+            Some(t) => t,
+            None => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        let id = match entry.id() {
+            Some(id) if target == ImportTarget::Finished || feed_ids.contains(&id) => id,
+            _ => {
+                report.unmapped.push(entry.title);
+                continue;
+            }
+        };
+        if following.following.contains_key(&id) || finished.following.contains_key(&id) {
+            report.existing += 1;
+            continue;
+        }
+        match target {
+            ImportTarget::Following => {
+                following.following.insert(id, entry.to_info());
+                report.following += 1;
+            }
+            ImportTarget::Finished => {
+                finished.following.insert(id, entry.to_info());
+                report.finished += 1;
+            }
+        }
+    }
+    report.unmapped.sort_unstable();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn mal_and_anilist_exports() -> Result<()> {
+        let mal = r#"<?xml version="1.0" encoding="UTF-8" ?>
+            <myanimelist>
+                <myinfo><user_name>someone</user_name></myinfo>
+                <anime>
+                    <series_animedb_id>52991</series_animedb_id>
+                    <series_title><![CDATA[Sousou no Frieren]]></series_title>
+                    <my_watched_episodes>12</my_watched_episodes>
+                    <my_status>Watching</my_status>
+                </anime>
+                <anime>
+                    <series_title><![CDATA[Cowboy Bebop]]></series_title>
+                    <my_watched_episodes>0</my_watched_episodes>
+                    <my_status>Plan to Watch</my_status>
+                </anime>
+            </myanimelist>"#;
+        let entries = parse(mal.as_bytes())?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "Sousou no Frieren");
+        assert_eq!(entries[0].watched, 12);
+        assert_eq!(entries[0].target, Some(ImportTarget::Following));
+        assert_eq!(entries[1].target, None);
+
+        let anilist = r#"{"data": {"MediaListCollection": {"lists": [{"entries": [
+            {"status": "COMPLETED", "progress": 26, "media": {
+                "title": {"romaji": "Shingeki no Kyojin", "english": "Attack on Titan"},
+                "season": "SPRING", "seasonYear": 2013}}
+        ]}]}}}"#;
+        let entries = parse(anilist.as_bytes())?;
+        assert_eq!(entries[0].target, Some(ImportTarget::Finished));
+        assert_eq!(entries[0].season, AnimeSeason::Spring(2013));
+        assert_eq!(entries[0].to_info().extra.en_name, "Attack on Titan");
+
+        assert!(parse(b"name,episodes").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn apply_maps_to_feed_ids() {
+        let entry = |title: &str, target| ImportEntry {
+            title: title.to_owned(),
+            en_title: None,
+            watched: 3,
+            season: AnimeSeason::Unknown,
+            target,
+        };
+        let airing = entry("Sousou no Frieren", Some(ImportTarget::Following));
+        let mut feed_ids = HashSet::new();
+        feed_ids.insert(airing.id().unwrap());
+        let mut following = Follows {
+            following: HashMap::new(),
+        };
+        let mut finished = Follows {
+            following: HashMap::new(),
+        };
+        let entries = vec![
+            airing.to_owned(),
+            airing,
+            entry("Unknown Show", Some(ImportTarget::Following)),
+            entry("Cowboy Bebop", Some(ImportTarget::Finished)),
+            entry("Monster", None),
+        ];
+        let report = apply(entries, &mut following, &mut finished, &feed_ids);
+        assert_eq!(report.following, 1);
+        assert_eq!(report.finished, 1);
+        assert_eq!(report.existing, 1);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.unmapped, vec!["Unknown Show".to_owned()]);
+        assert_eq!(finished.following.len(), 1);
+    }
+}
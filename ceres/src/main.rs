@@ -1,22 +1,55 @@
+use chrono::{Utc, Weekday};
+use chrono_tz::Tz;
+use clap::Parser;
 use feed_rs::parser;
+use fluent_bundle::FluentValue;
 use hyper::{body::HttpBody, Client};
 use hyper_tls::HttpsConnector;
 use regex::Regex;
 use scraper::{Html, Selector};
-use slug::slugify;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::str;
-use teloxide::dispatching::dialogue::InMemStorage;
+use std::str::{self, FromStr};
+use std::sync::Arc;
 use teloxide::dispatching::{dialogue, UpdateHandler};
+use teloxide::net::Download;
 use teloxide::types::InlineKeyboardButton;
 use teloxide::types::InlineKeyboardMarkup;
+use teloxide::types::InputFile;
+use teloxide::types::MessageId;
 use teloxide::{prelude::*, utils::command::BotCommands};
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
 mod anime;
-use anime::{AniInfo, AniMinInfo, Follows, Updates, ANIME_RAW, ANIME_RSS};
+mod callback;
+mod cli;
+mod digest;
+mod dryrun;
+mod export;
+mod i18n;
+mod import;
+mod migrations;
+mod render;
+mod schedule;
+mod settings;
+mod state;
+mod stats;
+mod storage;
+mod towatch;
+use anime::{
+    format_episodes, normalize_tag, series_id, AniEpisode, AniInfo, AniMinInfo, AniRelease,
+    AnimeSeason, AnimeVariant, Change, Follows, MemberProgress, Priority, Releases, SortBy,
+    Updates, ANIME_RAW, DEFAULT_EPISODE_MINUTES, MAX_SCORE, NOTE_MAX_CHARS, RSS_FEEDS,
+    SCRAP_MAX_PAGES, TAG_MAX_CHARS,
+};
+use callback::{CallbackAction, CallbackData, HandledPrompts};
+use export::ExportFormat;
+use i18n::{tr, tr_args, Lang};
+use import::{ImportEntry, ImportReport};
+use schedule::Airing;
+use state::State;
+use storage::{FileStorage, DIALOGUES_FILE};
+use towatch::WatchOrder;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 type HandlerResult = std::result::Result<(), Box<dyn std::error::Error + Send + Sync>>;
@@ -29,38 +62,118 @@ type HandlerResult = std::result::Result<(), Box<dyn std::error::Error + Send +
 enum Command {
     #[command(description = "shows this text.")]
     Help,
-    #[command(description = "checks if there are any anime updates.")]
-    CheckAnime,
-    #[command(description = "updates the viewing progress of a series.")]
-    UpdateAnime,
-    #[command(description = "shows the animes that we are following.")]
-    ShowFollowingAnime,
-    #[command(description = "shows the animes that we have finished.")]
-    ShowFinishedAnime,
-    #[command(description = "gives a to-watch list to catch up on.")]
-    ToWatch,
-    #[command(description = "marks a given anime as finished.")]
-    FinishAnime,
+    #[command(
+        description = "checks if there are any anime updates, \"dryrun\" only shows what would be notified."
+    )]
+    CheckAnime(String),
+    #[command(
+        description = "updates the viewing progress of a series, a tag only offers the series that have it."
+    )]
+    UpdateAnime(String),
+    #[command(
+        description = "shows the animes that we are following, \"season\" groups them by season and \"by name|season|progress|updated\" sorts them."
+    )]
+    ShowFollowingAnime(String),
+    #[command(
+        description = "shows the animes that we have finished, \"by name|season|progress|updated\" sorts them."
+    )]
+    ShowFinishedAnime(String),
+    #[command(
+        description = "gives a to-watch list to catch up on, \"by name|season|progress|updated|oldest|remaining|priority\" sorts it."
+    )]
+    ToWatch(String),
+    #[command(
+        description = "marks a given anime as finished, a tag only offers the series that have it."
+    )]
+    FinishAnime(String),
     #[command(description = "generates an id for a given name.")]
     GenId(String),
+    #[command(description = "sets the language of the bot for this chat.")]
+    Language(String),
+    #[command(description = "imports a MyAnimeList or AniList export.")]
+    Import,
+    #[command(description = "exports our lists as xml, csv or json.")]
+    Export(String),
+    #[command(description = "shows the episodes expected this week.")]
+    Schedule,
+    #[command(description = "sets when a series airs every week.")]
+    Airing(String),
+    #[command(description = "sets the timezone of this chat.")]
+    Timezone(String),
+    #[command(
+        description = "shows our series of a season, the current one by default, \"by name|season|progress|updated\" sorts them."
+    )]
+    Season(String),
+    #[command(
+        description = "sets the day of the weekly digest, \"off\" disables it and \"now\" sends it right away."
+    )]
+    Digest(String),
+    #[command(description = "shows how much we have watched and what is left.")]
+    Stats,
+    #[command(
+        description = "sets how many minutes the episodes of a series last, \"off\" goes back to 24."
+    )]
+    Duration(String),
+    #[command(description = "sets the priority of a series in /towatch: high, normal or low.")]
+    Priority(String),
+    #[command(description = "shows up to which episode of a series each of us has watched.")]
+    WhosWatched(String),
+    #[command(description = "gives a series that we finished a score from 1 to 10.")]
+    Rate(String),
+    #[command(description = "writes a note about a series that we finished, \"off\" removes it.")]
+    Note(String),
+    #[command(description = "adds a tag to a series, or removes it if the series already has it.")]
+    Tag(String),
+    #[command(
+        description = "shows the series that have a tag, or our tags if none is given, \"by name|season|progress|updated\" sorts them."
+    )]
+    List(String),
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 enum AnimeState {
     #[default]
     UpdateAnime,
     FinishAnime,
+    Import,
+    // airing to set on the series picked from the keyboard, None to clear it
+    SetAiring(Option<Airing>),
+    SetDuration(Option<u16>),
+    SetPriority(Priority),
+    // note to write on the series picked from the keyboard, None to remove it
+    SetNote(Option<String>),
+    // tag to add to the series picked from the keyboard, or to remove if it has it
+    SetTag(String),
 }
 
-static FOLLOWING_FILE: &str = "anime-following.json";
-static FINISHED_FILE: &str = "anime-finished.json";
-static UPDATES_FILE: &str = "anime-updates.json";
+type AnimeDialogue = Dialogue<AnimeState, FileStorage<AnimeState>>;
 
 #[tokio::main]
 async fn main() {
+    if let Some(command) = cli::Cli::parse().command {
+        if let Err(e) = cli::run(command).await {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
     let bot = Bot::from_env();
+    let storage = FileStorage::<AnimeState>::open(storage::path(DIALOGUES_FILE))
+        .await
+        .expect("Error opening dialogue storage");
+    let state = State::new();
+    // hand edits of the files are picked up while it lives
+    let _watcher = state
+        .watch()
+        .map_err(|e| log::error!("unable to watch the storage, edits need a restart: {}", e))
+        .ok();
+    tokio::spawn(digest::run(bot.clone(), state.clone()));
     Dispatcher::builder(bot, schema())
-        .dependencies(dptree::deps![InMemStorage::<AnimeState>::new()])
+        .dependencies(dptree::deps![
+            storage,
+            state,
+            Arc::new(HandledPrompts::default())
+        ])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
@@ -71,21 +184,36 @@ fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
     use dptree::case;
     let command_handler = teloxide::filter_command::<Command, _>()
         .branch(case![Command::Help].endpoint(command_help))
-        .branch(case![Command::CheckAnime].endpoint(command_check_anime))
-        .branch(case![Command::UpdateAnime].endpoint(command_update_anime))
-        .branch(case![Command::ShowFollowingAnime].endpoint(command_show_following_anime))
-        .branch(case![Command::ShowFinishedAnime].endpoint(command_show_finished_anime))
-        .branch(case![Command::ToWatch].endpoint(command_to_watch))
-        .branch(case![Command::FinishAnime].endpoint(command_finish_anime))
-        .branch(case![Command::GenId(anime)].endpoint(command_gen_id));
+        .branch(case![Command::CheckAnime(mode)].endpoint(command_check_anime))
+        .branch(case![Command::UpdateAnime(tag)].endpoint(command_update_anime))
+        .branch(case![Command::ShowFollowingAnime(args)].endpoint(command_show_following_anime))
+        .branch(case![Command::ShowFinishedAnime(args)].endpoint(command_show_finished_anime))
+        .branch(case![Command::ToWatch(args)].endpoint(command_to_watch))
+        .branch(case![Command::FinishAnime(tag)].endpoint(command_finish_anime))
+        .branch(case![Command::GenId(anime)].endpoint(command_gen_id))
+        .branch(case![Command::Language(lang)].endpoint(command_language))
+        .branch(case![Command::Import].endpoint(command_import))
+        .branch(case![Command::Export(format)].endpoint(command_export))
+        .branch(case![Command::Schedule].endpoint(command_schedule))
+        .branch(case![Command::Airing(airing)].endpoint(command_airing))
+        .branch(case![Command::Timezone(name)].endpoint(command_timezone))
+        .branch(case![Command::Season(args)].endpoint(command_season))
+        .branch(case![Command::Digest(day)].endpoint(command_digest))
+        .branch(case![Command::Stats].endpoint(command_stats))
+        .branch(case![Command::Duration(minutes)].endpoint(command_duration))
+        .branch(case![Command::Priority(priority)].endpoint(command_priority))
+        .branch(case![Command::WhosWatched(series)].endpoint(command_whos_watched))
+        .branch(case![Command::Rate(score)].endpoint(command_rate))
+        .branch(case![Command::Note(note)].endpoint(command_note))
+        .branch(case![Command::Tag(tag)].endpoint(command_tag))
+        .branch(case![Command::List(args)].endpoint(command_list));
     let message_handler = Update::filter_message()
         .branch(command_handler)
+        .branch(case![AnimeState::Import].endpoint(import_document))
         .branch(dptree::endpoint(invalid_state));
-    let callback_query_handler = Update::filter_callback_query()
-        .branch(case![AnimeState::UpdateAnime].endpoint(update_given_anime))
-        .branch(case![AnimeState::FinishAnime].endpoint(finish_given_anime));
+    let callback_query_handler = Update::filter_callback_query().endpoint(callback_router);
 
-    dialogue::enter::<Update, InMemStorage<AnimeState>, AnimeState, _>()
+    dialogue::enter::<Update, FileStorage<AnimeState>, AnimeState, _>()
         .branch(message_handler)
         .branch(callback_query_handler)
 }
@@ -108,45 +236,417 @@ async fn command_help(bot: Bot, msg: Message) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    bot.send_message(msg.chat.id, Command::descriptions().to_string())
-        .await?;
+    let lang = settings::chat_language(msg.chat.id).await;
+    let mut ret = tr(lang, "help-header") + "\n\n";
+    for command in Command::bot_commands() {
+        let key = format!("cmd-{}", command.command.trim_start_matches('/'));
+        ret.push_str(&format!("{} — {}\n", command.command, tr(lang, &key)));
+    }
+    bot.send_message(msg.chat.id, ret).await?;
+    Ok(())
+}
+
+/// handles /language {code}
+async fn command_language(bot: Bot, msg: Message, code: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let current = settings::chat_language(msg.chat.id).await;
+    if code.trim().is_empty() {
+        let text = tr_args(
+            current,
+            "language-current",
+            &[
+                ("language", current.to_string().into()),
+                ("available", Lang::available().into()),
+            ],
+        );
+        bot.send_message(msg.chat.id, text).await?;
+        return Ok(());
+    }
+    match code.parse::<Lang>() {
+        Ok(lang) => {
+            settings::update(|settings| settings.chat_mut(msg.chat.id).language = lang).await?;
+            bot.send_message(msg.chat.id, tr(lang, "language-set"))
+                .await?;
+        }
+        Err(unknown) => {
+            let text = tr_args(
+                current,
+                "language-unknown",
+                &[
+                    ("language", unknown.into()),
+                    ("available", Lang::available().into()),
+                ],
+            );
+            bot.send_message(msg.chat.id, text).await?;
+        }
+    }
     Ok(())
 }
 
-/// handles /checkanime
-async fn command_check_anime(bot: Bot, msg: Message) -> Result<()> {
+/// handles /checkanime [dryrun]
+async fn command_check_anime(
+    bot: Bot,
+    state: Arc<State>,
+    msg: Message,
+    mode: String,
+) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    check_updates(msg.chat.id, &bot).await
+    let lang = settings::chat_language(msg.chat.id).await;
+    match mode.trim() {
+        "" => check_updates(&state, msg.chat.id, &bot).await,
+        "dryrun" => {
+            let report = check_dry_run(&state, lang, true).await?;
+            render::send_html(&bot, msg.chat.id, &report).await
+        }
+        _ => {
+            bot.send_message(msg.chat.id, tr(lang, "checkanime-usage"))
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+async fn gen_following_keyboard(
+    state: &State,
+    action: CallbackAction,
+    tag: Option<&str>,
+) -> Result<InlineKeyboardMarkup> {
+    let mut following = state.snapshot().await?.following;
+    if let Some(tag) = tag {
+        following
+            .following
+            .retain(|_, info| info.extra.tags.contains(tag));
+    }
+    Ok(series_keyboard(following, |id, info| {
+        let data = CallbackData::new(action, id);
+        if action == CallbackAction::Update {
+            // the episode we are updating from, so that old buttons can't update twice
+            return data.with_arg(&info.info.last_episode.to_string());
+        }
+        data
+    }))
 }
 
-async fn gen_following_keyboard() -> InlineKeyboardMarkup {
-    let mut follows = get_follows_vec().await;
-    follows.sort_by_key(|k| k.1.to_owned());
+async fn gen_finished_keyboard(
+    state: &State,
+    action: CallbackAction,
+    arg: Option<&str>,
+) -> Result<InlineKeyboardMarkup> {
+    let finished = state.snapshot().await?.finished;
+    Ok(series_keyboard(finished, |id, _| {
+        let data = CallbackData::new(action, id);
+        match arg {
+            Some(arg) => data.with_arg(arg),
+            None => data,
+        }
+    }))
+}
+
+fn series_keyboard(
+    series: Follows,
+    data: impl Fn(&str, &AniInfo) -> CallbackData,
+) -> InlineKeyboardMarkup {
+    let mut series: Vec<(String, AniInfo)> = series.following.into_iter().collect();
+    series.sort_by_key(|k| k.1.extra.en_name.to_owned());
     let mut buttons: Vec<Vec<InlineKeyboardButton>> = Vec::new();
-    for f in follows {
-        let name = if f.1.len() <= 128 {
-            f.1
-        } else {
-            f.1[..128].to_owned()
-        };
-        buttons.push([InlineKeyboardButton::callback(name, f.0.to_string())].to_vec());
+    for (id, info) in series {
+        let data = data(&id, &info);
+        // cut on a character boundary, titles are often not ascii
+        let name: String = info.extra.en_name.chars().take(128).collect();
+        if let Some(data) = data.encode() {
+            buttons.push([InlineKeyboardButton::callback(name, data)].to_vec());
+        }
     }
     InlineKeyboardMarkup::new(buttons)
 }
 
-/// handles /updateanime
+// scores for a series, the buttons can be tapped again to change it
+fn score_keyboard(id: &str) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = (1..=MAX_SCORE)
+        .filter_map(|score| {
+            let data = CallbackData::new(CallbackAction::Rate, id).with_arg(&score.to_string());
+            Some(InlineKeyboardButton::callback(
+                score.to_string(),
+                data.encode()?,
+            ))
+        })
+        .collect();
+    InlineKeyboardMarkup::new(buttons.chunks(5).map(|row| row.to_vec()))
+}
+
+// thumbs up and down for an episode, the buttons can be tapped again to change it
+fn thumbs_keyboard(id: &str, episode: i16) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = [("👍", "up"), ("👎", "down")]
+        .into_iter()
+        .filter_map(|(label, thumb)| {
+            let data = CallbackData::new(CallbackAction::Thumb, id)
+                .with_arg(&format!("{episode}:{thumb}"));
+            Some(InlineKeyboardButton::callback(label, data.encode()?))
+        })
+        .collect();
+    InlineKeyboardMarkup::new([buttons])
+}
+
+/// Single entry point for keyboard buttons, dispatching on the action in their data.
+async fn callback_router(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    handled: Arc<HandledPrompts>,
+    q: CallbackQuery,
+) -> HandlerResult {
+    let prompt = q.message.as_ref().map(|m| m.id);
+    let data = q.data.as_deref().and_then(CallbackData::parse);
+    let is_prompt = data.as_ref().is_none_or(|d| d.action.is_prompt());
+    if is_prompt {
+        if let Some(message_id) = prompt {
+            if !handled.claim(dialogue.chat_id(), message_id) {
+                let lang = settings::chat_language(dialogue.chat_id()).await;
+                bot.answer_callback_query(q.id)
+                    .text(tr(lang, "prompt-handled"))
+                    .await?;
+                return Ok(());
+            }
+        }
+        // answer right away so that clients stop showing a spinner on the button
+        bot.answer_callback_query(q.id.to_owned()).await?;
+    }
+    match data {
+        Some(data) => match data.action {
+            CallbackAction::Update => {
+                let from_episode = data.arg.and_then(|a| a.parse::<i16>().ok());
+                update_given_anime(bot, dialogue, &state, prompt, data.id, from_episode).await
+            }
+            CallbackAction::Finish => {
+                finish_given_anime(bot, dialogue, &state, prompt, data.id).await
+            }
+            CallbackAction::Airing => {
+                airing_given_anime(bot, dialogue, &state, prompt, data.id).await
+            }
+            CallbackAction::Duration => {
+                duration_given_anime(bot, dialogue, &state, prompt, data.id).await
+            }
+            CallbackAction::Priority => {
+                priority_given_anime(bot, dialogue, &state, prompt, data.id).await
+            }
+            CallbackAction::Watched => {
+                let episode = data.arg.and_then(|a| a.parse::<i16>().ok());
+                watched_given_anime(bot, dialogue.chat_id(), &state, q, data.id, episode).await
+            }
+            CallbackAction::Rate => {
+                let score = data.arg.and_then(|a| a.parse::<u8>().ok());
+                rate_given_anime(bot, dialogue.chat_id(), &state, q, data.id, score).await
+            }
+            CallbackAction::Thumb => {
+                thumb_given_anime(bot, dialogue.chat_id(), &state, q, data.id, data.arg).await
+            }
+            CallbackAction::Note => note_given_anime(bot, dialogue, &state, prompt, data.id).await,
+            CallbackAction::Tag => tag_given_anime(bot, dialogue, &state, prompt, data.id).await,
+        },
+        None => outdated_callback(bot, dialogue, prompt).await,
+    }
+}
+
+// records how far whoever tapped the button of a notification has watched, answering
+// only to them so that the notification stays for the rest
+async fn watched_given_anime(
+    bot: Bot,
+    chat_id: ChatId,
+    state: &State,
+    q: CallbackQuery,
+    anime: String,
+    episode: Option<i16>,
+) -> HandlerResult {
+    let lang = settings::chat_language(chat_id).await;
+    let episode = match episode {
+        Some(episode) => episode,
+        None => {
+            bot.answer_callback_query(q.id)
+                .text(tr(lang, "outdated-button"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let member = q.from.full_name();
+    let marked = state
+        .update(|lists| {
+            let info = lists.series_mut(&anime)?;
+            let watched = info.extra.mark_watched(q.from.id.0, &member, episode);
+            Some((info.extra.en_name.to_owned(), watched))
+        })
+        .await?;
+    let text = match marked {
+        Some((title, episode)) => tr_args(
+            lang,
+            "watched-marked",
+            &[
+                ("member", member.into()),
+                ("title", title.into()),
+                ("episode", episode.into()),
+            ],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    bot.answer_callback_query(q.id)
+        .text(callback::answer_text(&text))
+        .await?;
+    Ok(())
+}
+
+// gives a series the score of the button, sent when finishing it or by /rate
+async fn rate_given_anime(
+    bot: Bot,
+    chat_id: ChatId,
+    state: &State,
+    q: CallbackQuery,
+    anime: String,
+    score: Option<u8>,
+) -> HandlerResult {
+    bot.answer_callback_query(q.id).await?;
+    let lang = settings::chat_language(chat_id).await;
+    let prompt = q.message.as_ref().map(|m| m.id);
+    let score = match score.filter(|s| (1..=MAX_SCORE).contains(s)) {
+        Some(score) => score,
+        None => {
+            answer_prompt(&bot, chat_id, prompt, &tr(lang, "outdated-button")).await?;
+            return Ok(());
+        }
+    };
+    let rated = state
+        .update(|lists| {
+            let info = lists.series_mut(&anime)?;
+            let previous = info.extra.score.replace(score);
+            Some((info.extra.en_name.to_owned(), previous))
+        })
+        .await?;
+    let title = match rated {
+        // Telegram refuses edits that don't change anything
+        Some((_, previous)) if previous == Some(score) => return Ok(()),
+        Some((title, _)) => title,
+        None => {
+            let text = tr_args(lang, "not-following", &[("anime", anime.as_str().into())]);
+            answer_prompt(&bot, chat_id, prompt, &text).await?;
+            return Ok(());
+        }
+    };
+    let text = tr_args(
+        lang,
+        "rated",
+        &[
+            ("title", render::title(&title).into()),
+            ("score", score.into()),
+        ],
+    );
+    // the keyboard stays so that the score can be changed
+    answer_prompt_with_keyboard(&bot, chat_id, prompt, &text, score_keyboard(&anime)).await?;
+    Ok(())
+}
+
+// records whether we liked an episode, from the buttons sent when updating to it
+async fn thumb_given_anime(
+    bot: Bot,
+    chat_id: ChatId,
+    state: &State,
+    q: CallbackQuery,
+    anime: String,
+    arg: Option<String>,
+) -> HandlerResult {
+    let lang = settings::chat_language(chat_id).await;
+    let thumb = arg.as_deref().and_then(|arg| match arg.split_once(':') {
+        Some((episode, "up")) => Some((episode.parse::<i16>().ok()?, true)),
+        Some((episode, "down")) => Some((episode.parse::<i16>().ok()?, false)),
+        _ => None,
+    });
+    let (episode, up) = match thumb {
+        Some(thumb) => thumb,
+        None => {
+            bot.answer_callback_query(q.id)
+                .text(tr(lang, "outdated-button"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let rated = state
+        .update(|lists| {
+            let info = lists.series_mut(&anime)?;
+            info.extra.thumbs.insert(episode, up);
+            Some(info.extra.en_name.to_owned())
+        })
+        .await?;
+    let text = match rated {
+        Some(title) => tr_args(
+            lang,
+            if up { "thumb-up" } else { "thumb-down" },
+            &[("title", title.into()), ("episode", episode.into())],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    bot.answer_callback_query(q.id)
+        .text(callback::answer_text(&text))
+        .await?;
+    Ok(())
+}
+
+/// Answers a keyboard prompt like `answer_prompt`, with a keyboard for what may follow.
+async fn answer_prompt_with_keyboard(
+    bot: &Bot,
+    chat_id: ChatId,
+    prompt: Option<MessageId>,
+    text: &str,
+    keyboard: InlineKeyboardMarkup,
+) -> Result<()> {
+    match prompt {
+        Some(message_id) => {
+            render::edit_html_with_keyboard(bot, chat_id, message_id, text, keyboard).await
+        }
+        None => render::send_html_with_keyboard(bot, chat_id, text, keyboard).await,
+    }
+}
+
+/// Answers a keyboard prompt replacing it with the outcome, so its buttons can't be
+/// tapped again. Falls back to a new message if the prompt is unknown.
+async fn answer_prompt(
+    bot: &Bot,
+    chat_id: ChatId,
+    prompt: Option<MessageId>,
+    text: &str,
+) -> Result<()> {
+    match prompt {
+        Some(message_id) => render::edit_html(bot, chat_id, message_id, text).await,
+        None => render::send_html(bot, chat_id, text).await,
+    }
+}
+
+/// handles /updateanime [tag]
 async fn command_update_anime(
     bot: Bot,
-    dialogue: Dialogue<AnimeState, InMemStorage<AnimeState>>,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
     msg: Message,
+    tag: String,
 ) -> HandlerResult {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    let animes = gen_following_keyboard().await;
-    bot.send_message(msg.chat.id, "Which anime do you want to update?")
+    let lang = settings::chat_language(msg.chat.id).await;
+    let tag = match tag_arg(lang, &tag) {
+        Ok(tag) => tag,
+        Err(text) => {
+            bot.send_message(msg.chat.id, text).await?;
+            return Ok(());
+        }
+    };
+    let animes = gen_following_keyboard(&state, CallbackAction::Update, tag.as_deref()).await?;
+    if let Some(tag) = tag.filter(|_| animes.inline_keyboard.is_empty()) {
+        let text = tr_args(lang, "tag-no-series", &[("tag", tag.into())]);
+        bot.send_message(msg.chat.id, text).await?;
+        return Ok(());
+    }
+    bot.send_message(msg.chat.id, tr(lang, "update-prompt"))
         .reply_markup(animes)
         .await?;
     dialogue.update(AnimeState::UpdateAnime).await?;
@@ -156,332 +656,1512 @@ async fn command_update_anime(
 // Works along with /updateanime to update the progress on a given anime series
 async fn update_given_anime(
     bot: Bot,
-    dialogue: Dialogue<AnimeState, InMemStorage<AnimeState>>,
-    q: CallbackQuery,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+    from_episode: Option<i16>,
 ) -> HandlerResult {
-    if let Some(anime) = &q.data {
-        let store_dir = env::var("BOT_STORAGE").expect("Error checking BOT_STORAGE");
-        let json_follows = store_dir.to_owned() + "/" + FOLLOWING_FILE;
-        let follows_content = tokio::fs::read(json_follows)
-            .await
-            .expect("Error reading following file");
-        let mut following: Follows =
-            serde_json::from_slice(&follows_content).expect("Error deserializing following json");
-        if !following.following.contains_key(anime) {
-            bot.send_message(
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    // Err if the progress changed since the keyboard was sent
+    let updated = state
+        .update(|lists| {
+            let info = lists.following.following.get_mut(&anime)?;
+            if from_episode.is_some_and(|e| e != info.info.last_episode) {
+                return Some(Err(info.to_owned()));
+            }
+            let now = Utc::now();
+            info.info.last_episode += 1;
+            info.info.updated = Some(now);
+            let info = info.to_owned();
+            let to = info.info.last_episode;
+            let watched = Change::Watched { from: to - 1, to };
+            lists.updates.record(&anime, watched, now);
+            Some(Ok(info))
+        })
+        .await?;
+    let info = match updated {
+        Some(Ok(info)) => info,
+        Some(Err(info)) => {
+            answer_prompt(
+                &bot,
                 dialogue.chat_id(),
-                format!("I couldn't find {anime} in our follows"),
+                prompt,
+                &tr_args(
+                    lang,
+                    "progress-changed",
+                    &[
+                        ("title", render::title(&info.extra.en_name).into()),
+                        ("episode", info.info.last_episode.into()),
+                    ],
+                ),
             )
             .await?;
             dialogue.exit().await?;
             return Ok(());
         }
+        None => {
+            answer_prompt(
+                &bot,
+                dialogue.chat_id(),
+                prompt,
+                &tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+            )
+            .await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+    let text = tr_args(
+        lang,
+        "updated-episode",
+        &[
+            ("title", render::title(&info.extra.en_name).into()),
+            ("episode", info.info.last_episode.into()),
+        ],
+    ) + "\n"
+        + &tr(lang, "thumbs-prompt");
+    let thumbs = thumbs_keyboard(&anime, info.info.last_episode);
+    answer_prompt_with_keyboard(&bot, dialogue.chat_id(), prompt, &text, thumbs).await?;
+    dialogue.exit().await?;
+    Ok(())
+}
 
-        let mut info = following.following.get(anime).unwrap().to_owned();
-        info.info.last_episode += 1;
-        following
-            .following
-            .insert(anime.to_owned(), info.to_owned());
+/// Splits the "by <order>" option off the arguments of a list command. Fails with the
+/// order that we don't know.
+fn sort_option<T>(args: &str) -> std::result::Result<(String, T), String>
+where
+    T: FromStr<Err = String> + Default,
+{
+    let words: Vec<&str> = args.split_whitespace().collect();
+    let pos = match words.iter().position(|w| w.eq_ignore_ascii_case("by")) {
+        Some(pos) => pos,
+        None => return Ok((words.join(" "), T::default())),
+    };
+    let order = words[pos + 1..].join(" ");
+    Ok((words[..pos].join(" "), order.parse::<T>()?))
+}
 
-        let mut file = File::create(store_dir.to_owned() + "/" + FOLLOWING_FILE).await?;
-        file.write_all(serde_json::to_string_pretty(&following)?.as_bytes())
+async fn sort_unknown(
+    bot: &Bot,
+    chat_id: ChatId,
+    lang: Lang,
+    order: String,
+    available: String,
+) -> Result<()> {
+    let text = tr_args(
+        lang,
+        "sort-unknown",
+        &[("order", order.into()), ("available", available.into())],
+    );
+    bot.send_message(chat_id, text).await?;
+    Ok(())
+}
+
+fn sorted_series<'a>(
+    series: impl Iterator<Item = (&'a String, &'a AniInfo)>,
+    updates: &Updates,
+    sort: SortBy,
+) -> Vec<&'a AniInfo> {
+    let mut series: Vec<(&String, &AniInfo)> = series.collect();
+    series.sort_unstable_by(|a, b| sort.compare((a.0, a.1), (b.0, b.1), updates));
+    series.into_iter().map(|(_, info)| info).collect()
+}
+
+/// handles /showfollowinganime [season] [by {order}]
+async fn command_show_following_anime(
+    bot: Bot,
+    state: Arc<State>,
+    msg: Message,
+    args: String,
+) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let (grouping, sort) = match sort_option(&args) {
+        Ok(option) => option,
+        Err(order) => {
+            return sort_unknown(&bot, msg.chat.id, lang, order, SortBy::available()).await
+        }
+    };
+    let by_season = match grouping.as_str() {
+        "" => false,
+        "season" => true,
+        _ => {
+            bot.send_message(msg.chat.id, tr(lang, "showfollowing-usage"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let lists = state.snapshot().await?;
+    let stuff = sorted_series(lists.following.following.iter(), &lists.updates, sort);
+    if stuff.is_empty() {
+        bot.send_message(msg.chat.id, tr(lang, "following-empty"))
             .await?;
-        bot.send_message(
-            dialogue.chat_id(),
-            format!(
-                "Updated '{}' to episode {}",
-                info.extra.en_name, info.info.last_episode
-            ),
-        )
-        .await?;
-        dialogue.exit().await?;
+        return Ok(());
+    }
+    let mut ret = tr(lang, "following-header") + "\n";
+    if by_season {
+        ret.push_str(&render::grouped_by_season(&stuff));
+    } else {
+        ret.push('\n');
+        for aniinfo in stuff {
+            ret.push_str(&render::series_line(aniinfo));
+        }
     }
+    render::send_html(&bot, msg.chat.id, &ret).await?;
     Ok(())
 }
 
-/// handles /showfollowinganime
-async fn command_show_following_anime(bot: Bot, msg: Message) -> Result<()> {
+/// handles /showfinishedanime [by {order}]
+async fn command_show_finished_anime(
+    bot: Bot,
+    state: Arc<State>,
+    msg: Message,
+    args: String,
+) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    let follows_data = read_from_storage("anime-following.json").await;
-    let following: Follows =
-        serde_json::from_slice(&follows_data).expect("Error deserializing follows json");
-    let mut stuff: Vec<AniInfo> = following.following.values().cloned().collect();
+    let lang = settings::chat_language(msg.chat.id).await;
+    let sort = match sort_option(&args) {
+        Ok((rest, sort)) if rest.is_empty() => sort,
+        Ok((rest, _)) | Err(rest) => {
+            return sort_unknown(&bot, msg.chat.id, lang, rest, SortBy::available()).await
+        }
+    };
+    let lists = state.snapshot().await?;
+    let stuff = sorted_series(lists.finished.following.iter(), &lists.updates, sort);
     if stuff.is_empty() {
-        bot.send_message(msg.chat.id, "We are not following any anime series.")
+        bot.send_message(msg.chat.id, tr(lang, "finished-empty"))
             .await?;
         return Ok(());
     }
-    stuff.sort_unstable();
-    let mut ret = "We are following these anime series:\n\n".to_string();
+    let mut ret = tr(lang, "finished-header") + "\n\n";
     for aniinfo in stuff {
-        ret.push_str(&format!(
-            "— {} [{}] - Ep. {}\n",
-            aniinfo.extra.en_name, aniinfo.extra.season, aniinfo.info.last_episode
-        ));
+        ret.push_str(&render::finished_line(aniinfo));
     }
-    bot.send_message(msg.chat.id, ret).await?;
+    render::send_html(&bot, msg.chat.id, &ret).await?;
     Ok(())
 }
 
-/// handles /showfinishedanime
-async fn command_show_finished_anime(bot: Bot, msg: Message) -> Result<()> {
+/// handles /season [season] [by {order}]
+async fn command_season(bot: Bot, state: Arc<State>, msg: Message, args: String) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    let follows_data = read_from_storage("anime-finished.json").await;
-    let following: Follows =
-        serde_json::from_slice(&follows_data).expect("Error deserializing finished json");
-    let mut stuff: Vec<AniInfo> = following.following.values().cloned().collect();
-    if stuff.is_empty() {
-        bot.send_message(msg.chat.id, "We haven't finished any anime series.")
+    let lang = settings::chat_language(msg.chat.id).await;
+    let (season, sort) = match sort_option(&args) {
+        Ok(option) => option,
+        Err(order) => {
+            return sort_unknown(&bot, msg.chat.id, lang, order, SortBy::available()).await
+        }
+    };
+    let season = match season.as_str() {
+        "" => AnimeSeason::current(),
+        season => match season.parse::<AnimeSeason>() {
+            Ok(season) => season,
+            Err(season) => {
+                bot.send_message(
+                    msg.chat.id,
+                    tr_args(lang, "season-unknown", &[("season", season.into())]),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+    let lists = state.snapshot().await?;
+    let mut ret = String::new();
+    for (header, follows) in [
+        ("season-following", &lists.following),
+        ("season-finished", &lists.finished),
+    ] {
+        let of_season = follows
+            .following
+            .iter()
+            .filter(|(_, s)| s.extra.season == season);
+        let stuff = sorted_series(of_season, &lists.updates, sort);
+        if stuff.is_empty() {
+            continue;
+        }
+        ret.push_str(&format!("\n{}\n", tr(lang, header)));
+        for aniinfo in stuff {
+            ret.push_str(&render::series_line(aniinfo));
+        }
+    }
+    let args = [("season", render::season(&season.to_string()).into())];
+    if ret.is_empty() {
+        render::send_html(&bot, msg.chat.id, &tr_args(lang, "season-empty", &args)).await?;
+        return Ok(());
+    }
+    ret.insert_str(0, &(tr_args(lang, "season-header", &args) + "\n"));
+    render::send_html(&bot, msg.chat.id, &ret).await?;
+    Ok(())
+}
+
+/// handles /finishanime [tag]
+async fn command_finish_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+    tag: String,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let tag = match tag_arg(lang, &tag) {
+        Ok(tag) => tag,
+        Err(text) => {
+            bot.send_message(msg.chat.id, text).await?;
+            return Ok(());
+        }
+    };
+    let animes = gen_following_keyboard(&state, CallbackAction::Finish, tag.as_deref()).await?;
+    if let Some(tag) = tag.filter(|_| animes.inline_keyboard.is_empty()) {
+        let text = tr_args(lang, "tag-no-series", &[("tag", tag.into())]);
+        bot.send_message(msg.chat.id, text).await?;
+        return Ok(());
+    }
+    bot.send_message(msg.chat.id, tr(lang, "finish-prompt"))
+        .reply_markup(animes)
+        .await?;
+    dialogue.update(AnimeState::FinishAnime).await?;
+    Ok(())
+}
+
+// works along with /finishanime to mark a given anime as finished
+async fn finish_given_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    // moves it from following to finished, both files are stored together
+    let moved = state
+        .update(|lists| {
+            if lists.finished.following.contains_key(&anime) {
+                return Err("already-finished");
+            }
+            let mut info = lists
+                .following
+                .following
+                .remove(&anime)
+                .ok_or("not-following")?;
+            let now = Utc::now();
+            info.info.updated = Some(now);
+            lists.updates.record(&anime, Change::Finished, now);
+            lists
+                .finished
+                .following
+                .insert(anime.to_owned(), info.to_owned());
+            Ok(info)
+        })
+        .await?;
+    let info = match moved {
+        Ok(info) => info,
+        Err(key) => {
+            answer_prompt(
+                &bot,
+                dialogue.chat_id(),
+                prompt,
+                &tr_args(lang, key, &[("anime", anime.as_str().into())]),
+            )
             .await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+    let text = tr_args(
+        lang,
+        "finished-added",
+        &[("title", render::title(&info.extra.en_name).into())],
+    ) + "\n"
+        + &tr(lang, "rate-finished");
+    answer_prompt_with_keyboard(
+        &bot,
+        dialogue.chat_id(),
+        prompt,
+        &text,
+        score_keyboard(&anime),
+    )
+    .await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+/// handles /towatch [by {order}]
+async fn command_to_watch(bot: Bot, state: Arc<State>, msg: Message, args: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    stuff.sort_unstable();
-    let mut ret = "We have finished these anime series:\n\n".to_string();
-    for aniinfo in stuff {
-        ret.push_str(&format!(
-            "— {} [{}] - Ep. {}\n",
-            aniinfo.extra.en_name, aniinfo.extra.season, aniinfo.info.last_episode
+    let lang = settings::chat_language(msg.chat.id).await;
+    let order = match sort_option::<WatchOrder>(&args) {
+        Ok((rest, order)) if rest.is_empty() => order,
+        Ok((rest, _)) | Err(rest) => {
+            return sort_unknown(&bot, msg.chat.id, lang, rest, WatchOrder::available()).await
+        }
+    };
+    let state::Lists {
+        following, updates, ..
+    } = state.snapshot().await?;
+    let pending = towatch::pending(&following, &updates);
+    let text = towatch::render(lang, pending, order, &updates);
+    render::send_html(&bot, msg.chat.id, &text).await?;
+    Ok(())
+}
+
+/// handles /genid {anime}
+async fn command_gen_id(bot: Bot, msg: Message, anime: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    render::send_html(
+        &bot,
+        msg.chat.id,
+        &render::code(&format!("id:{}", series_id(&anime))),
+    )
+    .await?;
+    Ok(())
+}
+
+/// handles /import
+async fn command_import(bot: Bot, dialogue: AnimeDialogue, msg: Message) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    bot.send_message(msg.chat.id, tr(lang, "import-prompt"))
+        .await?;
+    dialogue.update(AnimeState::Import).await?;
+    Ok(())
+}
+
+// works along with /import to read the export sent as a document
+async fn import_document(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let document = match msg.document() {
+        Some(d) => d,
+        None => {
+            bot.send_message(msg.chat.id, tr(lang, "import-prompt"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let file = bot.get_file(&document.file.id).await?;
+    let mut content: Vec<u8> = Vec::new();
+    bot.download_file(&file.path, &mut content).await?;
+    let entries = match import::parse(&content) {
+        Ok(entries) => entries,
+        Err(e) => {
+            bot.send_message(
+                msg.chat.id,
+                tr_args(lang, "import-invalid", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+    let report = import_entries(&state, entries).await?;
+    let mut ret = report.summary(lang) + "\n";
+    if !report.unmapped.is_empty() {
+        ret.push_str(&format!("\n{}\n", tr(lang, "import-unmapped-header")));
+        for title in report.unmapped.iter() {
+            ret.push_str(&format!("— {}\n", render::title(title)));
+        }
+    }
+    render::send_html(&bot, msg.chat.id, &ret).await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+/// Adds the imported entries to the following and finished lists. Entries to follow
+/// are matched against the series that we know from the updates and the feeds.
+async fn import_entries(state: &State, entries: Vec<ImportEntry>) -> Result<ImportReport> {
+    let state::Lists {
+        following, updates, ..
+    } = state.snapshot().await?;
+    let mut feed_ids: HashSet<String> = updates
+        .updates
+        .keys()
+        .map(|k| k.split(':').next().unwrap_or(k).to_owned())
+        .collect();
+    feed_ids.extend(
+        fetch_releases(&updates, &following)
+            .await?
+            .into_keys()
+            .map(|(id, _)| id),
+    );
+    let report = state
+        .update(|lists| {
+            import::apply(
+                entries,
+                &mut lists.following,
+                &mut lists.finished,
+                &feed_ids,
+            )
+        })
+        .await?;
+    Ok(report)
+}
+
+/// handles /export {format}
+async fn command_export(bot: Bot, state: Arc<State>, msg: Message, format: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let format = match format.parse::<ExportFormat>() {
+        Ok(f) => f,
+        Err(format) => {
+            bot.send_message(
+                msg.chat.id,
+                tr_args(
+                    lang,
+                    "export-unknown",
+                    &[
+                        ("format", format.into()),
+                        ("available", ExportFormat::available().into()),
+                    ],
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let lists = state.snapshot().await?;
+    let content = export::export(format, &lists.following, &lists.finished)?;
+    let file_name = format!("anime-export.{}", format.extension());
+    bot.send_document(msg.chat.id, InputFile::memory(content).file_name(file_name))
+        .await?;
+    Ok(())
+}
+
+/// handles /schedule
+async fn command_schedule(bot: Bot, state: Arc<State>, msg: Message) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let timezone = settings::chat_timezone(msg.chat.id).await;
+    let lists = state.snapshot().await?;
+    let text = schedule::week(lang, timezone, &lists.following, &lists.updates, Utc::now());
+    render::send_html(&bot, msg.chat.id, &text).await?;
+    Ok(())
+}
+
+/// handles /airing {weekday} {HH:MM} [timezone] and /airing off
+async fn command_airing(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+    airing: String,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let timezone = settings::chat_timezone(msg.chat.id).await;
+    let (airing, text) = match airing.trim() {
+        "off" => (None, tr(lang, "airing-clear-prompt")),
+        airing => match Airing::parse(airing, timezone) {
+            Ok(airing) => (
+                Some(airing),
+                tr_args(
+                    lang,
+                    "airing-prompt",
+                    &[("airing", airing.describe(lang).into())],
+                ),
+            ),
+            Err(_) => {
+                bot.send_message(msg.chat.id, tr(lang, "airing-usage"))
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+    let animes = gen_following_keyboard(&state, CallbackAction::Airing, None).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(animes)
+        .await?;
+    dialogue.update(AnimeState::SetAiring(airing)).await?;
+    Ok(())
+}
+
+// works along with /airing to set the airing time of the series picked
+async fn airing_given_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    let airing = match dialogue.get().await? {
+        Some(AnimeState::SetAiring(airing)) => airing,
+        _ => return outdated_callback(bot, dialogue, prompt).await,
+    };
+    let updated = state
+        .update(|lists| {
+            let info = lists.following.following.get_mut(&anime)?;
+            info.extra.airing = airing;
+            Some(info.to_owned())
+        })
+        .await?;
+    let text = match (updated, airing) {
+        (Some(info), Some(airing)) => tr_args(
+            lang,
+            "airing-set",
+            &[
+                ("title", render::title(&info.extra.en_name).into()),
+                ("airing", airing.describe(lang).into()),
+            ],
+        ),
+        (Some(info), None) => tr_args(
+            lang,
+            "airing-cleared",
+            &[("title", render::title(&info.extra.en_name).into())],
+        ),
+        (None, _) => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    answer_prompt(&bot, dialogue.chat_id(), prompt, &text).await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+/// handles /duration {minutes} and /duration off
+async fn command_duration(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+    minutes: String,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let (duration, text) = match minutes.trim() {
+        "off" => (None, tr(lang, "duration-clear-prompt")),
+        minutes => match minutes.parse::<u16>() {
+            Ok(minutes) if minutes > 0 => (
+                Some(minutes),
+                tr_args(lang, "duration-prompt", &[("minutes", minutes.into())]),
+            ),
+            _ => {
+                let text = tr_args(
+                    lang,
+                    "duration-usage",
+                    &[("default", DEFAULT_EPISODE_MINUTES.into())],
+                );
+                bot.send_message(msg.chat.id, text).await?;
+                return Ok(());
+            }
+        },
+    };
+    let animes = gen_following_keyboard(&state, CallbackAction::Duration, None).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(animes)
+        .await?;
+    dialogue.update(AnimeState::SetDuration(duration)).await?;
+    Ok(())
+}
+
+// works along with /duration to set the episode length of the series picked
+async fn duration_given_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    let duration = match dialogue.get().await? {
+        Some(AnimeState::SetDuration(duration)) => duration,
+        _ => return outdated_callback(bot, dialogue, prompt).await,
+    };
+    let updated = state
+        .update(|lists| {
+            let info = lists.following.following.get_mut(&anime)?;
+            info.extra.duration = duration;
+            Some(info.to_owned())
+        })
+        .await?;
+    let text = match updated {
+        Some(info) => tr_args(
+            lang,
+            if duration.is_some() {
+                "duration-set"
+            } else {
+                "duration-cleared"
+            },
+            &[
+                ("title", render::title(&info.extra.en_name).into()),
+                ("minutes", info.episode_minutes().into()),
+            ],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    answer_prompt(&bot, dialogue.chat_id(), prompt, &text).await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+/// handles /priority {high|normal|low}
+async fn command_priority(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+    priority: String,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let priority = match priority.parse::<Priority>() {
+        Ok(priority) => priority,
+        Err(_) => {
+            bot.send_message(msg.chat.id, tr(lang, "priority-usage"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let text = tr_args(
+        lang,
+        "priority-prompt",
+        &[("priority", priority_name(lang, priority).into())],
+    );
+    let animes = gen_following_keyboard(&state, CallbackAction::Priority, None).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(animes)
+        .await?;
+    dialogue.update(AnimeState::SetPriority(priority)).await?;
+    Ok(())
+}
+
+// works along with /priority to set the priority of the series picked
+async fn priority_given_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    let priority = match dialogue.get().await? {
+        Some(AnimeState::SetPriority(priority)) => priority,
+        _ => return outdated_callback(bot, dialogue, prompt).await,
+    };
+    let updated = state
+        .update(|lists| {
+            let info = lists.following.following.get_mut(&anime)?;
+            info.extra.priority = priority;
+            Some(info.to_owned())
+        })
+        .await?;
+    let text = match updated {
+        Some(info) => tr_args(
+            lang,
+            "priority-set",
+            &[
+                ("title", render::title(&info.extra.en_name).into()),
+                ("priority", priority_name(lang, priority).into()),
+            ],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    answer_prompt(&bot, dialogue.chat_id(), prompt, &text).await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+fn priority_name(lang: Lang, priority: Priority) -> String {
+    tr(lang, &format!("priority-{priority}"))
+}
+
+/// handles /whoswatched {series}
+async fn command_whos_watched(
+    bot: Bot,
+    state: Arc<State>,
+    msg: Message,
+    series: String,
+) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    if series.trim().is_empty() {
+        bot.send_message(msg.chat.id, tr(lang, "whoswatched-usage"))
+            .await?;
+        return Ok(());
+    }
+    let lists = state.snapshot().await?;
+    let (id, info) = match lists.find(&series)[..] {
+        [found] => found,
+        [] => {
+            let args = [("series", render::escape(series.trim()).into())];
+            let text = tr_args(lang, "whoswatched-unknown", &args);
+            render::send_html(&bot, msg.chat.id, &text).await?;
+            return Ok(());
+        }
+        ref several => {
+            let mut text = tr(lang, "whoswatched-ambiguous") + "\n";
+            for (_, info) in several {
+                text.push_str(&format!("— {}\n", render::title(&info.extra.en_name)));
+            }
+            render::send_html(&bot, msg.chat.id, &text).await?;
+            return Ok(());
+        }
+    };
+    let title = render::title(&info.extra.en_name);
+    let mut text = tr_args(
+        lang,
+        "whoswatched-header",
+        &[("title", title.to_owned().into())],
+    );
+    text.push('\n');
+    text.push_str(&tr_args(
+        lang,
+        "whoswatched-together",
+        &[("episode", info.info.last_episode.into())],
+    ));
+    text.push('\n');
+    let mut members: Vec<&MemberProgress> = info.extra.members.values().collect();
+    members.sort_unstable_by(|a, b| {
+        b.last_episode
+            .cmp(&a.last_episode)
+            .then(a.name.cmp(&b.name))
+    });
+    for member in members {
+        let line = tr_args(
+            lang,
+            "whoswatched-member",
+            &[
+                ("member", render::escape(&member.name).into()),
+                ("episode", member.last_episode.into()),
+            ],
+        );
+        text.push_str(&(line + "\n"));
+    }
+    if info.extra.members.is_empty() {
+        text.push_str(&(tr(lang, "whoswatched-nobody") + "\n"));
+    }
+    if let Some(released) = lists
+        .updates
+        .updates
+        .get(&info.extra.variant.update_key(id))
+    {
+        text.push_str(&tr_args(
+            lang,
+            "whoswatched-released",
+            &[("episode", released.last_episode.into())],
         ));
     }
-    bot.send_message(msg.chat.id, ret).await?;
+    render::send_html(&bot, msg.chat.id, &text).await?;
+    Ok(())
+}
+
+/// handles /rate {score}
+async fn command_rate(bot: Bot, state: Arc<State>, msg: Message, score: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let score = match score.trim().parse::<u8>() {
+        Ok(score) if (1..=MAX_SCORE).contains(&score) => score,
+        _ => {
+            bot.send_message(msg.chat.id, tr(lang, "rate-usage"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let arg = score.to_string();
+    let animes = gen_finished_keyboard(&state, CallbackAction::Rate, Some(&arg)).await?;
+    bot.send_message(
+        msg.chat.id,
+        tr_args(lang, "rate-prompt", &[("score", score.into())]),
+    )
+    .reply_markup(animes)
+    .await?;
+    Ok(())
+}
+
+/// handles /note {text} and /note off
+async fn command_note(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
+    msg: Message,
+    note: String,
+) -> HandlerResult {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let (note, text) = match note.trim() {
+        "" => {
+            bot.send_message(msg.chat.id, tr(lang, "note-usage"))
+                .await?;
+            return Ok(());
+        }
+        "off" => (None, tr(lang, "note-clear-prompt")),
+        note if note.chars().count() > NOTE_MAX_CHARS => {
+            let text = tr_args(lang, "note-too-long", &[("max", NOTE_MAX_CHARS.into())]);
+            bot.send_message(msg.chat.id, text).await?;
+            return Ok(());
+        }
+        note => (Some(note.to_owned()), tr(lang, "note-prompt")),
+    };
+    let animes = gen_finished_keyboard(&state, CallbackAction::Note, None).await?;
+    bot.send_message(msg.chat.id, text)
+        .reply_markup(animes)
+        .await?;
+    dialogue.update(AnimeState::SetNote(note)).await?;
+    Ok(())
+}
+
+// works along with /note to write the note on the series picked
+async fn note_given_anime(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    let note = match dialogue.get().await? {
+        Some(AnimeState::SetNote(note)) => note,
+        _ => return outdated_callback(bot, dialogue, prompt).await,
+    };
+    let cleared = note.is_none();
+    let updated = state
+        .update(|lists| {
+            let info = lists.series_mut(&anime)?;
+            info.extra.note = note;
+            Some(info.extra.en_name.to_owned())
+        })
+        .await?;
+    let text = match updated {
+        Some(title) => tr_args(
+            lang,
+            if cleared { "note-cleared" } else { "note-set" },
+            &[("title", render::title(&title).into())],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    answer_prompt(&bot, dialogue.chat_id(), prompt, &text).await?;
+    dialogue.exit().await?;
     Ok(())
 }
 
-/// handles /finishanime
-async fn command_finish_anime(
+/// handles /tag {tag}
+async fn command_tag(
     bot: Bot,
-    dialogue: Dialogue<AnimeState, InMemStorage<AnimeState>>,
+    dialogue: AnimeDialogue,
+    state: Arc<State>,
     msg: Message,
+    tag: String,
 ) -> HandlerResult {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    let animes = gen_following_keyboard().await;
-    bot.send_message(msg.chat.id, "Which anime have you finished?")
+    let lang = settings::chat_language(msg.chat.id).await;
+    let tag = match tag_arg(lang, &tag) {
+        Ok(Some(tag)) => tag,
+        Ok(None) => {
+            bot.send_message(msg.chat.id, tr(lang, "tag-usage")).await?;
+            return Ok(());
+        }
+        Err(text) => {
+            bot.send_message(msg.chat.id, text).await?;
+            return Ok(());
+        }
+    };
+    // we may tag the series that we finished too
+    let lists = state.snapshot().await?;
+    let mut series = lists.following;
+    series.following.extend(lists.finished.following);
+    let animes = series_keyboard(series, |id, _| CallbackData::new(CallbackAction::Tag, id));
+    let text = tr_args(lang, "tag-prompt", &[("tag", tag.as_str().into())]);
+    bot.send_message(msg.chat.id, text)
         .reply_markup(animes)
         .await?;
-    dialogue.update(AnimeState::FinishAnime).await?;
+    dialogue.update(AnimeState::SetTag(tag)).await?;
     Ok(())
 }
 
-// works along with /finishanime to mark a given anime as finished
-async fn finish_given_anime(
+// works along with /tag to add the tag to the series picked, or remove it
+async fn tag_given_anime(
     bot: Bot,
-    dialogue: Dialogue<AnimeState, InMemStorage<AnimeState>>,
-    q: CallbackQuery,
+    dialogue: AnimeDialogue,
+    state: &State,
+    prompt: Option<MessageId>,
+    anime: String,
 ) -> HandlerResult {
-    if let Some(anime) = &q.data {
-        let store_dir = env::var("BOT_STORAGE").expect("Error checking BOT_STORAGE");
-        // get following
-        let json_follows = store_dir.to_owned() + "/" + FOLLOWING_FILE;
-        let follows_content = tokio::fs::read(json_follows)
-            .await
-            .expect("Error reading following file");
-        let mut following: Follows =
-            serde_json::from_slice(&follows_content).expect("Error deserializing following json");
-        // get finished
-        let json_finished = store_dir.to_owned() + "/" + FINISHED_FILE;
-        let finished_content = tokio::fs::read(json_finished)
-            .await
-            .expect("Error reading finished file");
-        let mut finished: Follows =
-            serde_json::from_slice(&finished_content).expect("Error deserializing finished json");
-        if finished.following.contains_key(anime) {
-            bot.send_message(
-                dialogue.chat_id(),
-                format!("You already have '{anime}' in our finished list"),
-            )
-            .await?;
-            dialogue.exit().await?;
-            return Ok(());
-        }
-        // add it to finished
-        let info = following.following.get(anime).unwrap().clone();
-        finished.following.insert(anime.to_owned(), info.to_owned());
-        let mut file_finished = File::create(store_dir.to_owned() + "/" + FINISHED_FILE).await?;
-        file_finished
-            .write_all(serde_json::to_string_pretty(&finished)?.as_bytes())
-            .await?;
-        // remove from following and update
-        following.following.remove(anime);
-        let mut file_following = File::create(store_dir.to_owned() + "/" + FOLLOWING_FILE).await?;
-        file_following
-            .write_all(serde_json::to_string_pretty(&following)?.as_bytes())
-            .await?;
-        bot.send_message(
-            dialogue.chat_id(),
-            format!(
-                "'{}' has been added to the finished list.",
-                info.extra.en_name
-            ),
-        )
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    let tag = match dialogue.get().await? {
+        Some(AnimeState::SetTag(tag)) => tag,
+        _ => return outdated_callback(bot, dialogue, prompt).await,
+    };
+    let updated = state
+        .update(|lists| {
+            let info = lists.series_mut(&anime)?;
+            let added = info.extra.tags.insert(tag.to_owned());
+            if !added {
+                info.extra.tags.remove(&tag);
+            }
+            Some((info.extra.en_name.to_owned(), added))
+        })
         .await?;
-    } else {
-        bot.send_message(dialogue.chat_id(), "Did not get an anime")
-            .await?;
-    }
+    let text = match updated {
+        Some((title, added)) => tr_args(
+            lang,
+            if added { "tag-added" } else { "tag-removed" },
+            &[
+                ("title", render::title(&title).into()),
+                ("tag", render::code(&tag).into()),
+            ],
+        ),
+        None => tr_args(lang, "not-following", &[("anime", anime.as_str().into())]),
+    };
+    answer_prompt(&bot, dialogue.chat_id(), prompt, &text).await?;
     dialogue.exit().await?;
     Ok(())
 }
 
-/// handles /towatch
-async fn command_to_watch(bot: Bot, msg: Message) -> Result<()> {
+/// Tag given to a command, None if it wasn't given any. Fails with the text telling that
+/// it isn't a valid tag.
+fn tag_arg(lang: Lang, args: &str) -> std::result::Result<Option<String>, String> {
+    if args.trim().is_empty() {
+        return Ok(None);
+    }
+    normalize_tag(args)
+        .map(Some)
+        .ok_or_else(|| tr_args(lang, "tag-invalid", &[("max", TAG_MAX_CHARS.into())]))
+}
+
+/// handles /list [tag] [by {order}]
+async fn command_list(bot: Bot, state: Arc<State>, msg: Message, args: String) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    let follows_data = read_from_storage("anime-following.json").await;
-    let following: Follows =
-        serde_json::from_slice(&follows_data).expect("Error deserializing following json");
-    let updates_data = read_from_storage("anime-updates.json").await;
-    let updates: Updates =
-        serde_json::from_slice(&updates_data).expect("Error deserializing updates json");
-    let mut towatch: Vec<(String, String)> = Vec::new();
-    for (id, ani) in following.following {
-        if updates.updates.contains_key(&id)
-            && ani.info.last_episode < updates.updates.get(&id).unwrap().last_episode
-        {
-            if ani.info.last_episode + 1 == updates.updates.get(&id).unwrap().last_episode {
-                towatch.push((
-                    ani.extra.en_name,
-                    format!(
-                        "just Ep. {}",
-                        updates.updates.get(&id).unwrap().last_episode
-                    ),
-                ));
-            } else {
-                towatch.push((
-                    ani.extra.en_name,
-                    format!(
-                        "from {} up to Ep.{}",
-                        ani.info.last_episode + 1,
-                        updates.updates.get(&id).unwrap().last_episode
-                    ),
-                ));
+    let lang = settings::chat_language(msg.chat.id).await;
+    let (tag, sort) = match sort_option(&args) {
+        Ok((tag, sort)) => (tag, sort),
+        Err(order) => {
+            return sort_unknown(&bot, msg.chat.id, lang, order, SortBy::available()).await
+        }
+    };
+    let tag = match tag_arg(lang, &tag) {
+        Ok(tag) => tag,
+        Err(text) => {
+            bot.send_message(msg.chat.id, text).await?;
+            return Ok(());
+        }
+    };
+    let lists = state.snapshot().await?;
+    let tag = match tag {
+        Some(tag) => tag,
+        // without a tag, the ones that we use
+        None => {
+            let tags = lists.tags();
+            if tags.is_empty() {
+                bot.send_message(msg.chat.id, tr(lang, "tags-none")).await?;
+                return Ok(());
+            }
+            let mut ret = tr(lang, "tags-header") + "\n\n";
+            for (tag, count) in tags {
+                ret.push_str(&format!("— {}: {count}\n", render::code(tag)));
             }
+            return render::send_html(&bot, msg.chat.id, &ret).await;
         }
+    };
+    let tagged = |(_, info): &(&String, &AniInfo)| info.extra.tags.contains(&tag);
+    let following = sorted_series(
+        lists.following.following.iter().filter(tagged),
+        &lists.updates,
+        sort,
+    );
+    let finished = sorted_series(
+        lists.finished.following.iter().filter(tagged),
+        &lists.updates,
+        sort,
+    );
+    let code = render::code(&tag);
+    if following.is_empty() && finished.is_empty() {
+        let text = tr_args(lang, "tag-no-series", &[("tag", code.into())]);
+        return render::send_html(&bot, msg.chat.id, &text).await;
     }
-    if !towatch.is_empty() {
-        towatch.sort_unstable();
-        let mut ret = "This is our watchlist:\n".to_string();
-        for (ani, desc) in towatch {
-            ret.push_str(&format!("· {ani}\n    → {desc}\n"));
+    let mut ret = tr_args(lang, "list-header", &[("tag", code.into())]) + "\n";
+    for (key, series) in [("list-following", following), ("list-finished", finished)] {
+        if series.is_empty() {
+            continue;
+        }
+        ret.push_str(&format!("\n{}\n", tr(lang, key)));
+        for aniinfo in series {
+            ret.push_str(&render::series_line(aniinfo));
         }
-        bot.send_message(msg.chat.id, ret).await?;
-    } else {
-        bot.send_message(
-            msg.chat.id,
-            "We are up to date according to the latest Update data.",
-        )
-        .await?;
     }
+    render::send_html(&bot, msg.chat.id, &ret).await
+}
 
+/// handles /stats
+async fn command_stats(bot: Bot, state: Arc<State>, msg: Message) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let lang = settings::chat_language(msg.chat.id).await;
+    let lists = state.snapshot().await?;
+    render::send_html(&bot, msg.chat.id, &stats::render(lang, &lists)).await?;
     Ok(())
 }
 
-/// handles /genid {anime}
-async fn command_gen_id(bot: Bot, msg: Message, anime: String) -> Result<()> {
+/// handles /timezone {name}
+async fn command_timezone(bot: Bot, msg: Message, name: String) -> Result<()> {
     if !is_allowed_user(msg.chat.id) {
         return Ok(());
     }
-    bot.send_message(
-        msg.chat.id,
-        format!("id:{:x}", md5::compute(slugify(anime))),
+    let chat = settings::load().await?.chat(msg.chat.id);
+    let lang = chat.language;
+    if name.trim().is_empty() {
+        let text = tr_args(
+            lang,
+            "timezone-current",
+            &[("timezone", chat.timezone().name().into())],
+        );
+        bot.send_message(msg.chat.id, text).await?;
+        return Ok(());
+    }
+    let text = match name.trim().parse::<Tz>() {
+        Ok(timezone) => {
+            settings::update(|settings| settings.chat_mut(msg.chat.id).timezone = Some(timezone))
+                .await?;
+            tr_args(
+                lang,
+                "timezone-set",
+                &[("timezone", timezone.name().into())],
+            )
+        }
+        Err(_) => tr_args(
+            lang,
+            "timezone-unknown",
+            &[("timezone", name.trim().to_owned().into())],
+        ),
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+/// handles /digest [weekday|off|now]
+async fn command_digest(bot: Bot, state: Arc<State>, msg: Message, day: String) -> Result<()> {
+    if !is_allowed_user(msg.chat.id) {
+        return Ok(());
+    }
+    let chat = settings::load().await?.chat(msg.chat.id);
+    let lang = chat.language;
+    let when = |day: Weekday| -> [(&'static str, FluentValue<'static>); 3] {
+        [
+            ("day", schedule::weekday_name(lang, day).into()),
+            ("hour", digest::DIGEST_HOUR.into()),
+            ("timezone", chat.timezone().name().into()),
+        ]
+    };
+    let text = match day.trim() {
+        "" => match chat.digest {
+            Some(day) => tr_args(lang, "digest-current", &when(day)),
+            None => tr(lang, "digest-off"),
+        },
+        "now" => {
+            digest::send(&bot, &state, msg.chat.id, &chat, Utc::now()).await?;
+            return Ok(());
+        }
+        "off" => {
+            settings::update(|settings| settings.chat_mut(msg.chat.id).digest = None).await?;
+            tr(lang, "digest-disabled")
+        }
+        day => match day.parse::<Weekday>() {
+            Ok(day) => {
+                settings::update(|settings| {
+                    let chat = settings.chat_mut(msg.chat.id);
+                    chat.digest = Some(day);
+                    // the first one is the next scheduled, not one that we missed
+                    chat.last_digest = Some(Utc::now());
+                })
+                .await?;
+                tr_args(lang, "digest-set", &when(day))
+            }
+            Err(_) => tr_args(lang, "digest-unknown", &[("day", day.to_owned().into())]),
+        },
+    };
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+/// handles the buttons of keyboards whose callback data we can't make sense of anymore
+async fn outdated_callback(
+    bot: Bot,
+    dialogue: AnimeDialogue,
+    prompt: Option<MessageId>,
+) -> HandlerResult {
+    let lang = settings::chat_language(dialogue.chat_id()).await;
+    answer_prompt(
+        &bot,
+        dialogue.chat_id(),
+        prompt,
+        &tr(lang, "outdated-button"),
     )
     .await?;
+    dialogue.exit().await?;
     Ok(())
 }
 
 async fn invalid_state(bot: Bot, msg: Message) -> HandlerResult {
-    bot.send_message(
-        msg.chat.id,
-        "Unable to handle the message. Type /help to see the usage.",
-    )
-    .await?;
+    let lang = settings::chat_language(msg.chat.id).await;
+    bot.send_message(msg.chat.id, tr(lang, "invalid-state"))
+        .await?;
     Ok(())
 }
 
-async fn check_updates(chat_id: ChatId, bot: &Bot) -> Result<()> {
-    let updates_content = read_from_storage("anime-updates.json").await;
-    let updates: Updates =
-        serde_json::from_slice(&updates_content).expect("Error deserializing update json");
-    let follows_content = read_from_storage("anime-following.json").await;
-    let following: Follows =
-        serde_json::from_slice(&follows_content).expect("Error deserializing following json");
+async fn check_updates(state: &State, chat_id: ChatId, bot: &Bot) -> Result<()> {
+    let lang = settings::chat_language(chat_id).await;
+    let state::Lists {
+        following, updates, ..
+    } = state.snapshot().await?;
 
-    let mut eps = fetch_rss().await?;
-    if eps.is_empty() {
-        log::info!("switching to scraper");
-        eps = scrap_updates().await?;
-    }
-    // we care about the ones that we are following, and out of those, the new updates
-    let mut store_update: HashMap<&String, &AniMinInfo> = HashMap::new();
-    let mut message_update: HashMap<&String, &AniMinInfo> = HashMap::new();
-    let mut new_series: Vec<&String> = Vec::new();
-    for (id, ani) in eps.iter() {
-        if !following.following.contains_key(id) {
-            if ani.last_episode == 1 {
-                new_series.push(&ani.name);
-            }
-            continue;
-        }
-        if (!updates.updates.contains_key(id)
-            && ani.last_episode > following.following.get(id).unwrap().info.last_episode)
-            || (updates.updates.contains_key(id)
-                && ani.last_episode > updates.updates.get(id).unwrap().last_episode)
-        {
-            store_update.insert(id, ani);
-            message_update.insert(&following.following.get(id).unwrap().extra.en_name, ani);
-        }
-    }
-    if message_update.values().len() == 0 && new_series.is_empty() {
-        bot.send_message(chat_id, "There are no updates!")
+    let eps = fetch_releases(&updates, &following).await?;
+    let pending = pending_updates(&eps, &following, &updates);
+    let late = schedule::late_series(&following, &eps, &updates, Utc::now());
+    if pending.is_empty() && late.is_empty() {
+        bot.send_message(chat_id, tr(lang, "no-updates"))
             .await
             .unwrap();
     } else {
-        let mut message: String = "This is the latest anime update:\n\n".to_owned();
+        let mut message: String = if pending.is_empty() {
+            tr(lang, "no-updates") + "\n"
+        } else {
+            tr(lang, "updates-header") + "\n\n"
+        };
         let mut up = false;
-        for (ename, info) in message_update {
-            message.push_str(&format!(
-                "— Ep. {} for '{}' is out ({})\n",
-                info.last_episode, ename, info.name
-            ));
+        for (ename, (info, new_episodes)) in pending.episodes {
+            let numbers: Vec<i16> = new_episodes.iter().map(|e| e.number).collect();
+            let line = tr_args(
+                lang,
+                "update-line",
+                &[
+                    ("episodes", format_episodes(&numbers).into()),
+                    ("title", render::title(ename).into()),
+                    ("name", render::escape(&info.name).into()),
+                ],
+            );
+            message.push_str(&(line + "\n"));
+            let links: Vec<String> = new_episodes
+                .iter()
+                .filter_map(|e| render::episode_link(e))
+                .collect();
+            if !links.is_empty() {
+                message.push_str(&format!("    → {}\n", links.join(" · ")));
+            }
             up = true;
         }
-        if !new_series.is_empty() {
+        if !pending.new_series.is_empty() {
             if up {
                 message.push('\n');
             }
-            message.push_str("We have new series coming up!\n");
-            for series in new_series {
-                message.push_str(&format!("— {series}\n"));
+            message.push_str(&(tr(lang, "new-series-header") + "\n"));
+            for series in pending.new_series {
+                message.push_str(&format!("— {}\n", render::title(series)));
             }
         }
-        bot.send_message(chat_id, message).await?;
-        sync_updates(updates, store_update).await?;
+        if !late.is_empty() {
+            let timezone = settings::chat_timezone(chat_id).await;
+            message.push('\n');
+            message.push_str(&schedule::late_warning(lang, timezone, &late));
+        }
+        match watched_keyboard(lang, &following, &pending.released) {
+            Some(keyboard) => {
+                render::send_html_with_keyboard(bot, chat_id, &message, keyboard).await?
+            }
+            None => render::send_html(bot, chat_id, &message).await?,
+        }
+        let now = Utc::now();
+        state
+            .update(|lists| {
+                for (key, mut info) in pending.store {
+                    info.updated = Some(now);
+                    lists.updates.updates.insert(key, info);
+                }
+                for (id, episodes) in pending.released {
+                    lists
+                        .updates
+                        .record(&id, Change::Released { episodes }, now);
+                }
+            })
+            .await?;
     }
     Ok(())
 }
 
-async fn sync_updates(mut updates: Updates, notify: HashMap<&String, &AniMinInfo>) -> Result<()> {
-    for (id, info) in notify {
-        updates.updates.insert(id.to_owned(), info.to_owned());
+/// Buttons for each member to mark the episodes notified as watched, None if no episode
+/// was notified.
+fn watched_keyboard(
+    lang: Lang,
+    following: &Follows,
+    released: &[(String, Vec<i16>)],
+) -> Option<InlineKeyboardMarkup> {
+    let mut buttons: Vec<(String, String)> = Vec::new();
+    for (id, episodes) in released {
+        let (info, episode) = match (following.following.get(id), episodes.iter().max()) {
+            (Some(info), Some(episode)) => (info, *episode),
+            _ => continue,
+        };
+        let data = CallbackData::new(CallbackAction::Watched, id).with_arg(&episode.to_string());
+        if let Some(data) = data.encode() {
+            let label = tr_args(
+                lang,
+                "watched-button",
+                &[
+                    ("title", info.extra.en_name.as_str().into()),
+                    ("episode", episode.into()),
+                ],
+            );
+            buttons.push((label, data));
+        }
+    }
+    if buttons.is_empty() {
+        return None;
+    }
+    buttons.sort_unstable();
+    let buttons = buttons
+        .into_iter()
+        .map(|(label, data)| vec![InlineKeyboardButton::callback(label, data)]);
+    Some(InlineKeyboardMarkup::new(buttons))
+}
+
+/// Renders what a check would notify and how the releases of every source matched our
+/// follows, without notifying or recording anything.
+async fn check_dry_run(state: &State, lang: Lang, html: bool) -> Result<String> {
+    let state::Lists {
+        following, updates, ..
+    } = state.snapshot().await?;
+    let sources = fetch_sources(&updates).await?;
+    let releases = checked_releases(&sources, &following);
+    let pending = pending_updates(&releases, &following, &updates);
+    Ok(dryrun::report(
+        lang, &sources, &following, &updates, &pending, html,
+    ))
+}
+
+/// What a check of the releases has to notify.
+struct PendingUpdates<'a> {
+    // latest release of each notified series, keyed like `Updates`
+    store: HashMap<String, AniMinInfo>,
+    // English name of the series -> its release and the episodes that weren't notified
+    episodes: HashMap<&'a String, (&'a AniRelease, Vec<&'a AniEpisode>)>,
+    new_series: Vec<&'a String>,
+    // id of the series -> numbers of the episodes that weren't notified
+    released: Vec<(String, Vec<i16>)>,
+}
+
+impl PendingUpdates<'_> {
+    fn is_empty(&self) -> bool {
+        self.episodes.is_empty() && self.new_series.is_empty()
+    }
+}
+
+/// Compares the releases with the series that we follow and the episodes that were
+/// already notified.
+fn pending_updates<'a>(
+    eps: &'a Releases,
+    following: &'a Follows,
+    updates: &Updates,
+) -> PendingUpdates<'a> {
+    // we care about the ones that we are following, and out of those, the new updates
+    let mut store_update: HashMap<String, AniMinInfo> = HashMap::new();
+    let mut message_update: HashMap<&String, (&AniRelease, Vec<&AniEpisode>)> = HashMap::new();
+    let mut new_series: Vec<&String> = Vec::new();
+    let mut released: Vec<(String, Vec<i16>)> = Vec::new();
+    for ((id, variant), ani) in eps.iter() {
+        match match_release(id, *variant, ani, following, updates) {
+            ReleaseMatch::NotFollowed { premiere: true } => new_series.push(&ani.name),
+            ReleaseMatch::New(followed, new_episodes) => {
+                store_update.insert(variant.update_key(id), ani.to_min_info());
+                released.push((
+                    id.to_owned(),
+                    new_episodes.iter().map(|e| e.number).collect(),
+                ));
+                message_update.insert(&followed.extra.en_name, (ani, new_episodes));
+            }
+            _ => {}
+        }
+    }
+    PendingUpdates {
+        store: store_update,
+        episodes: message_update,
+        new_series,
+        released,
+    }
+}
+
+/// How a release relates to the series that we follow.
+#[derive(Debug, PartialEq)]
+enum ReleaseMatch<'a> {
+    // premiere is true for the first episode of a sub release, which we announce
+    NotFollowed { premiere: bool },
+    // we follow the series, but watch another variant
+    OtherVariant(AnimeVariant),
+    // last notified episode
+    UpToDate(i16),
+    New(&'a AniInfo, Vec<&'a AniEpisode>),
+}
+
+fn match_release<'a>(
+    id: &str,
+    variant: AnimeVariant,
+    release: &'a AniRelease,
+    following: &'a Follows,
+    updates: &Updates,
+) -> ReleaseMatch<'a> {
+    let followed = match following.following.get(id) {
+        Some(f) => f,
+        None => {
+            return ReleaseMatch::NotFollowed {
+                premiere: variant.is_sub() && release.episodes.iter().any(|e| e.number == 1),
+            }
+        }
+    };
+    // only the variant that we watch for each series is notified
+    if followed.extra.variant != variant {
+        return ReleaseMatch::OtherVariant(followed.extra.variant);
+    }
+    let known_episode = match updates.updates.get(&variant.update_key(id)) {
+        Some(update) => update.last_episode,
+        None => followed.info.last_episode,
+    };
+    let new_episodes = release.episodes_after(known_episode);
+    if new_episodes.is_empty() {
+        ReleaseMatch::UpToDate(known_episode)
+    } else {
+        ReleaseMatch::New(followed, new_episodes)
+    }
+}
+
+/// Releases of every source on its own, the scraper included.
+async fn fetch_sources(updates: &Updates) -> Result<Vec<(&'static str, Releases)>> {
+    let mut sources = Vec::new();
+    for (name, (url, variant)) in ["rss sub", "rss dub"].into_iter().zip(RSS_FEEDS) {
+        sources.push((name, fetch_feed(url, variant).await?));
+    }
+    sources.push(("scraper", scrap_updates(updates).await?));
+    Ok(sources)
+}
+
+// the releases that a real check would use out of those of every source, picked like
+// fetch_releases does
+fn checked_releases(sources: &[(&str, Releases)], following: &Follows) -> Releases {
+    let feeds: Vec<(AnimeVariant, Releases)> = RSS_FEEDS
+        .iter()
+        .zip(sources)
+        .map(|((_, variant), (_, feed))| (*variant, feed.to_owned()))
+        .collect();
+    let missing = scraped_variants(&feeds, following);
+    let scraped = sources
+        .get(RSS_FEEDS.len())
+        .map(|(_, scraped)| scraped.to_owned())
+        .unwrap_or_default();
+    combine_releases(feeds, scraped, &missing)
+}
+
+/// Latest releases from the RSS feeds. The releases of a variant whose feed is down, and
+/// those of the raw series that no feed serves, come from the scraper instead.
+async fn fetch_releases(updates: &Updates, following: &Follows) -> Result<Releases> {
+    let mut feeds: Vec<(AnimeVariant, Releases)> = Vec::new();
+    for (url, feed_variant) in RSS_FEEDS {
+        feeds.push((feed_variant, fetch_feed(url, feed_variant).await?));
+    }
+    let missing = scraped_variants(&feeds, following);
+    let scraped = if missing.is_empty() {
+        HashMap::new()
+    } else {
+        log::info!("switching to scraper for {:?}", missing);
+        scrap_updates(updates).await?
+    };
+    Ok(combine_releases(feeds, scraped, &missing))
+}
+
+// variants that have to come from the scraper: those whose feed gave us nothing, and
+// raw, which no feed serves, if we follow a raw series
+fn scraped_variants(feeds: &[(AnimeVariant, Releases)], following: &Follows) -> Vec<AnimeVariant> {
+    let mut missing: Vec<AnimeVariant> = feeds
+        .iter()
+        .filter(|(_, feed)| feed.is_empty())
+        .map(|(variant, _)| *variant)
+        .collect();
+    let raw = AnimeVariant::Raw;
+    if following
+        .following
+        .values()
+        .any(|info| info.extra.variant == raw)
+    {
+        missing.push(raw);
+    }
+    missing
+}
+
+/// Releases of the feeds along with the scraped ones of the variants that they miss.
+fn combine_releases(
+    feeds: Vec<(AnimeVariant, Releases)>,
+    scraped: Releases,
+    missing: &[AnimeVariant],
+) -> Releases {
+    let mut releases: Releases = HashMap::new();
+    for (_, feed) in feeds {
+        add_releases(&mut releases, feed);
+    }
+    add_releases(
+        &mut releases,
+        scraped
+            .into_iter()
+            .filter(|((_, variant), _)| missing.contains(variant)),
+    );
+    releases
+}
+
+/// Adds the releases of another source, merging the episodes of the series in both.
+fn add_releases(
+    releases: &mut Releases,
+    more: impl IntoIterator<Item = ((String, AnimeVariant), AniRelease)>,
+) {
+    for (key, release) in more {
+        match releases.get_mut(&key) {
+            Some(r) => r.merge(release),
+            None => {
+                releases.insert(key, release);
+            }
+        }
     }
-    let store_dir = env::var("BOT_STORAGE").expect("Error checking BOT_STORAGE");
-    let json = serde_json::to_string_pretty(&updates)?;
-    let mut file = File::create(store_dir.to_owned() + "/" + UPDATES_FILE).await?;
-    file.write_all(json.as_bytes()).await?;
-    Ok(())
 }
 
 async fn _fetch_url(url: &str) -> Result<String> {
     let https = HttpsConnector::new();
     let client = Client::builder().build::<_, hyper::Body>(https);
     let uri = url.parse()?;
-    let mut resp = client.get(uri).await.expect("Error fetching RSS");
+    let mut resp = client.get(uri).await?;
     let mut stuff = String::new();
     while let Some(next) = resp.data().await {
         let chunk = next?;
@@ -490,22 +2170,68 @@ async fn _fetch_url(url: &str) -> Result<String> {
     Ok(stuff)
 }
 
-async fn scrap_updates() -> Result<HashMap<String, AniMinInfo>> {
-    let mut updates: HashMap<String, AniMinInfo> = HashMap::new();
-    let html_content = match _fetch_url(ANIME_RAW).await {
+/// Follows the pagination of the recent releases listing until it reaches releases that
+/// we have already recorded in `updates` or hits `SCRAP_MAX_PAGES`.
+async fn scrap_updates(updates: &Updates) -> Result<Releases> {
+    let mut releases: Releases = HashMap::new();
+    for page in 1..=SCRAP_MAX_PAGES {
+        let page_releases = scrap_page(page).await?;
+        if page_releases.is_empty() {
+            break;
+        }
+        if merge_releases(&mut releases, page_releases, updates) {
+            log::info!("scraper caught up with the recorded updates on page {page}");
+            break;
+        }
+    }
+    Ok(releases)
+}
+
+/// Merges the releases of a page grouping the episodes per series. Returns true
+/// if the page contains a release that was already recorded in `updates`.
+fn merge_releases(
+    releases: &mut Releases,
+    page_releases: Vec<((String, AnimeVariant), AniRelease)>,
+    updates: &Updates,
+) -> bool {
+    let mut caught_up = false;
+    for ((id, variant), release) in page_releases {
+        if let Some(known) = updates.updates.get(&variant.update_key(&id)) {
+            if release.last_episode() <= known.last_episode {
+                caught_up = true;
+            }
+        }
+        match releases.get_mut(&(id.to_owned(), variant)) {
+            Some(r) => r.merge(release),
+            None => {
+                releases.insert((id, variant), release);
+            }
+        }
+    }
+    caught_up
+}
+
+async fn scrap_page(page: u16) -> Result<Vec<((String, AnimeVariant), AniRelease)>> {
+    let url = format!("{ANIME_RAW}?page={page}");
+    let html_content = match _fetch_url(&url).await {
         Ok(val) => val,
         Err(e) => {
-            log::error!("unable to fetch {}: {}", ANIME_RAW, e);
-            return Ok(updates);
+            log::error!("unable to fetch {}: {}", url, e);
+            return Ok(Vec::new());
         }
     };
-    let document = scraper::Html::parse_document(&html_content);
+    Ok(parse_last_episodes(&html_content))
+}
+
+fn parse_last_episodes(html_content: &str) -> Vec<((String, AnimeVariant), AniRelease)> {
+    let mut updates: Vec<((String, AnimeVariant), AniRelease)> = Vec::new();
+    let document = scraper::Html::parse_document(html_content);
     let select_last_eps = Selector::parse("div.last_episodes").unwrap();
     let result = match document.select(&select_last_eps).next() {
         Some(r) => r,
         None => {
             log::error!("'div.last_episodes' selector did not find a match");
-            return Ok(updates);
+            return updates;
         }
     };
     let select_items = Selector::parse("li").unwrap();
@@ -517,7 +2243,7 @@ async fn scrap_updates() -> Result<HashMap<String, AniMinInfo>> {
             Some(r) => r,
             None => {
                 log::error!("'p.episode' selector did not find a match");
-                return Ok(updates);
+                return updates;
             }
         };
         let episode = match ep.text().collect::<Vec<_>>().first() {
@@ -551,6 +2277,13 @@ async fn scrap_updates() -> Result<HashMap<String, AniMinInfo>> {
                 continue;
             }
         };
+        let link = a.value().attr("href").map(|r| {
+            if r.starts_with("http") {
+                r.to_owned()
+            } else {
+                format!("{}{}", ANIME_RAW.trim_end_matches('/'), r)
+            }
+        });
         let href = match a.value().attr("href") {
             Some(r) => {
                 if let Some(rr) = re_href.captures(r) {
@@ -572,6 +2305,23 @@ async fn scrap_updates() -> Result<HashMap<String, AniMinInfo>> {
                 continue;
             }
         };
+        let (title_variant, title) = AnimeVariant::split_title(title);
+        let select_type = Selector::parse("div.type").unwrap();
+        let variant = match item
+            .select(&select_type)
+            .next()
+            .and_then(|t| t.value().classes().find(|c| c.starts_with("ic-")))
+        {
+            Some("ic-DUB") => AnimeVariant::Dub,
+            Some("ic-RAW") => AnimeVariant::Raw,
+            Some(_) => AnimeVariant::Sub,
+            None => title_variant,
+        };
+        // dubbed releases live under "<series>-dub", we want the id of the series
+        let href = match variant {
+            AnimeVariant::Dub => href.strip_suffix("-dub").unwrap_or(href),
+            _ => href,
+        };
         if let Some(ep) = re_episode.captures(&episode) {
             let last_episode = ep.get(1).map_or("", |m| m.as_str());
             let last_episode = match last_episode.parse::<i16>() {
@@ -581,68 +2331,73 @@ async fn scrap_updates() -> Result<HashMap<String, AniMinInfo>> {
                     continue;
                 }
             };
-            updates.insert(
-                format!("{:x}", md5::compute(href)),
-                AniMinInfo {
-                    name: String::from(title),
-                    last_episode,
-                },
-            );
+            updates.push((
+                (format!("{:x}", md5::compute(href)), variant),
+                AniRelease::new(title, AniEpisode::new(last_episode, link, None)),
+            ));
         }
     }
-    Ok(updates)
+    updates
 }
 
-async fn fetch_rss() -> Result<HashMap<String, AniMinInfo>> {
-    let mut updates: HashMap<String, AniMinInfo> = HashMap::new();
+/// Releases of a single RSS feed, empty if it can't be fetched.
+async fn fetch_feed(url: &str, feed_variant: AnimeVariant) -> Result<Releases> {
+    match _fetch_url(url).await {
+        Ok(content) => Ok(parse_feed(&content, feed_variant)),
+        Err(e) => {
+            log::error!("unable to fetch {}: {}", url, e);
+            Ok(HashMap::new())
+        }
+    }
+}
 
-    let feed = match _fetch_url(ANIME_RSS).await {
-        Ok(val) => match parser::parse(val.as_bytes()) {
-            Ok(f) => f,
-            Err(_) => return Ok(updates),
-        },
-        Err(_) => return Ok(updates),
+/// Releases listed in the content of a feed. Entries that don't look like an episode of
+/// a series, like "Episode 12.5", are skipped.
+fn parse_feed(content: &str, feed_variant: AnimeVariant) -> Releases {
+    let mut updates: Releases = HashMap::new();
+    let re = Regex::new(r"([\w\W\s]+) - Episode ([\d\D]+)").unwrap();
+    let feed = match parser::parse(content.as_bytes()) {
+        Ok(f) => f,
+        Err(e) => {
+            log::error!("unable to parse a feed: {}", e);
+            return updates;
+        }
     };
 
-    let re = Regex::new(r"([\w\W\s]+) - Episode ([\d\D]+)").unwrap();
     for et in feed.entries {
-        if let Some(info) = re.captures(&et.title.unwrap().content) {
+        let link = et.links.first().map(|l| l.href.to_owned());
+        let published = et.published.or(et.updated);
+        let Some(title) = et.title else { continue };
+        if let Some(info) = re.captures(&title.content) {
             let episode = info.get(2).map_or("", |m| m.as_str());
-            let series = info.get(1).map_or("", |m| m.as_str());
-            updates.insert(
-                format!("{:x}", md5::compute(slugify(series))),
-                AniMinInfo {
-                    name: String::from(series),
-                    last_episode: episode.parse::<i16>().unwrap(),
-                },
-            );
+            let Ok(episode) = episode.trim().parse::<i16>() else {
+                continue;
+            };
+            let (variant, series) =
+                AnimeVariant::split_title(info.get(1).map_or("", |m| m.as_str()));
+            let variant = if variant.is_sub() {
+                feed_variant
+            } else {
+                variant
+            };
+            let episode = AniEpisode::new(episode, link, published);
+            let key = (series_id(series), variant);
+            match updates.get_mut(&key) {
+                Some(r) => r.add_episode(episode),
+                None => {
+                    updates.insert(key, AniRelease::new(series, episode));
+                }
+            }
         }
     }
-    Ok(updates)
-}
-
-async fn get_follows_vec() -> Vec<(String, String)> {
-    let follows_content = read_from_storage("anime-following.json").await;
-    let following: Follows =
-        serde_json::from_slice(&follows_content).expect("Error deserializing following json");
-    let mut ret: Vec<(String, String)> = vec![];
-    for (key, val) in following.following {
-        ret.push((key, val.extra.en_name));
-    }
-    ret
-}
-
-async fn read_from_storage(file_name: &str) -> Vec<u8> {
-    let store_dir = env::var("BOT_STORAGE").expect("Error checking BOT_STORAGE");
-    let path = store_dir.to_owned() + "/" + file_name;
-    tokio::fs::read(path)
-        .await
-        .expect("Error reading {file_name}")
+    updates
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anime::ANIME_RSS;
+    use teloxide::types::InlineKeyboardButtonKind;
 
     #[tokio::test]
     async fn xml_required_fields() -> Result<()> {
@@ -721,8 +2476,273 @@ mod tests {
 
     #[tokio::test]
     async fn test_scraping() -> Result<()> {
-        let updates = scrap_updates().await?;
+        let updates = scrap_page(1).await?;
         assert_eq!(updates.len(), 20);
         Ok(())
     }
+
+    #[test]
+    fn scraping_pagination_merge() {
+        let release = |name: &str, last_episode: i16| {
+            AniRelease::new(name, AniEpisode::new(last_episode, None, None))
+        };
+        let key = |id: &str| (id.to_owned(), AnimeVariant::Sub);
+        let updates = Updates {
+            updates: HashMap::from([("b".to_owned(), release("B", 3).to_min_info())]),
+            history: Vec::new(),
+        };
+        let mut releases = HashMap::new();
+        let first_page = vec![
+            (key("a"), release("A", 6)),
+            (key("b"), release("B", 4)),
+            (("b".to_owned(), AnimeVariant::Dub), release("B", 1)),
+        ];
+        assert!(!merge_releases(&mut releases, first_page, &updates));
+        let second_page = vec![(key("a"), release("A", 5)), (key("b"), release("B", 3))];
+        assert!(merge_releases(&mut releases, second_page, &updates));
+        assert_eq!(releases.len(), 3);
+        assert_eq!(releases.get(&key("a")).unwrap().episodes.len(), 2);
+        assert_eq!(releases.get(&key("b")).unwrap().last_episode(), 4);
+    }
+
+    #[test]
+    fn scraper_fills_missing_feeds() {
+        let release = |name: &str, last_episode: i16| {
+            AniRelease::new(name, AniEpisode::new(last_episode, None, None))
+        };
+        let sub = |id: &str| (id.to_owned(), AnimeVariant::Sub);
+        let dub = |id: &str| (id.to_owned(), AnimeVariant::Dub);
+        let raw = |id: &str| (id.to_owned(), AnimeVariant::Raw);
+        let mut following: Follows = serde_json::from_str(
+            r#"{"following": {"a": {"info": {"name": "A", "last_episode": 1},
+                "extra": {"en_name": "A", "season": "Unknown"}}}}"#,
+        )
+        .unwrap();
+        // the sub feed is down, the dub one answered
+        let feeds = || {
+            vec![
+                (AnimeVariant::Sub, HashMap::new()),
+                (
+                    AnimeVariant::Dub,
+                    HashMap::from([(dub("a"), release("A", 2))]),
+                ),
+            ]
+        };
+        let scraped = || -> Releases {
+            HashMap::from([
+                (sub("a"), release("A", 5)),
+                (sub("b"), release("B", 1)),
+                (dub("a"), release("A", 3)),
+                (raw("a"), release("A", 6)),
+            ])
+        };
+        let missing = scraped_variants(&feeds(), &following);
+        assert_eq!(missing, [AnimeVariant::Sub]);
+        let releases = combine_releases(feeds(), scraped(), &missing);
+        assert_eq!(releases.len(), 3);
+        assert_eq!(releases[&sub("a")].last_episode(), 5);
+        // the dub feed already knew about the dubs
+        assert_eq!(releases[&dub("a")].last_episode(), 2);
+
+        // no feed serves raw releases
+        following.following.get_mut("a").unwrap().extra.variant = AnimeVariant::Raw;
+        let missing = scraped_variants(&feeds(), &following);
+        assert_eq!(missing, [AnimeVariant::Sub, AnimeVariant::Raw]);
+        let releases = combine_releases(feeds(), scraped(), &missing);
+        assert_eq!(releases[&raw("a")].last_episode(), 6);
+
+        // the dry run sees every source and picks the same releases as a check
+        let [(_, sub_feed), (_, dub_feed)] = <[_; 2]>::try_from(feeds()).unwrap();
+        let sources = [
+            ("rss sub", sub_feed),
+            ("rss dub", dub_feed),
+            ("scraper", scraped()),
+        ];
+        assert_eq!(checked_releases(&sources, &following), releases);
+    }
+
+    #[test]
+    fn release_episodes() {
+        let link = Some("https://example.org/a-episode-6".to_owned());
+        let mut release = AniRelease::new("A", AniEpisode::new(6, None, None));
+        release.add_episode(AniEpisode::new(5, None, None));
+        release.add_episode(AniEpisode::new(6, link.to_owned(), None));
+        release.add_episode(AniEpisode::new(2, None, None));
+        let numbers = |eps: Vec<&AniEpisode>| eps.iter().map(|e| e.number).collect::<Vec<_>>();
+        assert_eq!(numbers(release.episodes_after(0)), vec![2, 5, 6]);
+        assert_eq!(release.last_episode(), 6);
+        assert_eq!(release.episodes.last().unwrap().link, link);
+        assert_eq!(
+            format_episodes(&numbers(release.episodes_after(4))),
+            "Ep. 5–6"
+        );
+        assert_eq!(
+            format_episodes(&numbers(release.episodes_after(5))),
+            "Ep. 6"
+        );
+        assert_eq!(
+            format_episodes(&numbers(release.episodes_after(0))),
+            "Ep. 2, 5–6"
+        );
+    }
+
+    #[test]
+    fn season_parsing_and_order() {
+        for text in ["Spring 2024", "spring 2024", "2024-spring", "2024 Spring"] {
+            assert_eq!(text.parse::<AnimeSeason>(), Ok(AnimeSeason::Spring(2024)));
+        }
+        assert_eq!("2023-fall".parse(), Ok(AnimeSeason::Autumn(2023)));
+        assert!("2024".parse::<AnimeSeason>().is_err());
+        assert!("2024-monsoon".parse::<AnimeSeason>().is_err());
+        let mut seasons = vec![
+            AnimeSeason::Unknown,
+            AnimeSeason::Winter(2025),
+            AnimeSeason::Autumn(2024),
+            AnimeSeason::Spring(2024),
+        ];
+        seasons.sort();
+        assert_eq!(
+            seasons,
+            vec![
+                AnimeSeason::Spring(2024),
+                AnimeSeason::Autumn(2024),
+                AnimeSeason::Winter(2025),
+                AnimeSeason::Unknown
+            ]
+        );
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+        assert_eq!(AnimeSeason::of_date(date), AnimeSeason::Autumn(2024));
+    }
+
+    #[test]
+    fn list_sort_options() {
+        assert_eq!(sort_option(""), Ok((String::new(), SortBy::Name)));
+        assert_eq!(
+            sort_option("Winter 2025 by progress"),
+            Ok(("Winter 2025".to_owned(), SortBy::Progress))
+        );
+        assert_eq!(sort_option::<SortBy>("by colour"), Err("colour".to_owned()));
+
+        let following: Follows = serde_json::from_str(
+            r#"{"following": {
+                "a": {"info": {"name": "A", "last_episode": 3,
+                               "updated": "2024-04-01T10:00:00Z"},
+                      "extra": {"en_name": "A", "season": {"Winter": 2024}}},
+                "b": {"info": {"name": "B", "last_episode": 9},
+                      "extra": {"en_name": "B", "season": "Unknown"}},
+                "c": {"info": {"name": "C", "last_episode": 1},
+                      "extra": {"en_name": "C", "season": {"Spring": 2024}}}}}"#,
+        )
+        .unwrap();
+        let updates: Updates = serde_json::from_str(
+            r#"{"updates": {"c": {"name": "C", "last_episode": 2,
+                                  "updated": "2024-04-02T10:00:00Z"}}}"#,
+        )
+        .unwrap();
+        let names = |sort| {
+            sorted_series(following.following.iter(), &updates, sort)
+                .iter()
+                .map(|s| s.extra.en_name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(SortBy::Name), vec!["A", "B", "C"]);
+        assert_eq!(names(SortBy::Season), vec!["C", "A", "B"]);
+        assert_eq!(names(SortBy::Progress), vec!["B", "A", "C"]);
+        assert_eq!(names(SortBy::Updated), vec!["C", "A", "B"]);
+    }
+
+    #[test]
+    fn feed_entries() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="2.0"><channel><title>Releases</title>
+            <item><title>Some name - Episode 3</title>
+                <link>https://example.org/some-name-episode-3</link></item>
+            <item><title>Other name (Dub) - Episode 2</title></item>
+            <item><title>Some name - Episode 12.5</title></item>
+            <item><title>Some name - Episode 4 END</title></item>
+            <item><description>no title</description></item>
+            </channel></rss>"#;
+        let releases = parse_feed(feed, AnimeVariant::Sub);
+        assert_eq!(releases.len(), 2);
+        let some = &releases[&(series_id("Some name"), AnimeVariant::Sub)];
+        assert_eq!(some.last_episode(), 3);
+        assert!(releases.contains_key(&(series_id("Other name"), AnimeVariant::Dub)));
+        assert!(parse_feed("not a feed", AnimeVariant::Sub).is_empty());
+    }
+
+    #[test]
+    fn long_titles_in_keyboards() {
+        let mut info: AniInfo = serde_json::from_str(
+            r#"{"info": {"name": "a", "last_episode": 1},
+                "extra": {"en_name": "", "season": "Unknown"}}"#,
+        )
+        .unwrap();
+        // three bytes each, byte 128 falls in the middle of one
+        info.extra.en_name = "葬".repeat(200);
+        let series = Follows {
+            following: HashMap::from([("a".to_owned(), info)]),
+        };
+        let keyboard = series_keyboard(series, |id, _| {
+            CallbackData::new(CallbackAction::Update, id)
+        });
+        assert_eq!(keyboard.inline_keyboard[0][0].text, "葬".repeat(128));
+    }
+
+    #[test]
+    fn watched_buttons() {
+        let mut following: Follows = serde_json::from_str(
+            r#"{"following": {
+                "a": {"info": {"name": "A", "last_episode": 2},
+                      "extra": {"en_name": "A", "season": "Unknown"}},
+                "b": {"info": {"name": "B", "last_episode": 7},
+                      "extra": {"en_name": "B", "season": "Unknown"}}}}"#,
+        )
+        .unwrap();
+        let released = vec![
+            ("a".to_owned(), vec![3, 5, 4]),
+            ("b".to_owned(), vec![8]),
+            // not followed anymore
+            ("c".to_owned(), vec![1]),
+        ];
+        let keyboard = watched_keyboard(Lang::En, &following, &released).unwrap();
+        let buttons: Vec<(&str, Option<CallbackData>)> = keyboard
+            .inline_keyboard
+            .iter()
+            .map(|row| match &row[0].kind {
+                InlineKeyboardButtonKind::CallbackData(data) => {
+                    (row[0].text.as_str(), CallbackData::parse(data))
+                }
+                _ => (row[0].text.as_str(), None),
+            })
+            .collect();
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0].0, "✓ A Ep. 5");
+        let data = CallbackData::new(CallbackAction::Watched, "a").with_arg("5");
+        assert_eq!(buttons[0].1, Some(data));
+        assert!(watched_keyboard(Lang::En, &following, &[]).is_none());
+
+        // each member keeps the furthest episode that they marked
+        let extra = &mut following.following.get_mut("a").unwrap().extra;
+        assert_eq!(extra.mark_watched(1, "Ana", 5), 5);
+        assert_eq!(extra.mark_watched(2, "Jon", 3), 3);
+        assert_eq!(extra.mark_watched(1, "Ana M.", 4), 5);
+        assert_eq!(extra.members[&1].name, "Ana M.");
+        assert_eq!(extra.members[&1].last_episode, 5);
+        assert_eq!(extra.members[&2].last_episode, 3);
+    }
+
+    #[test]
+    fn release_variants() {
+        assert_eq!(
+            AnimeVariant::split_title("Some name (Dub)"),
+            (AnimeVariant::Dub, "Some name")
+        );
+        assert_eq!(
+            AnimeVariant::split_title("Some name"),
+            (AnimeVariant::Sub, "Some name")
+        );
+        let id = "098f6bcd4621d373cade4e832627b4f6";
+        assert_eq!(AnimeVariant::Sub.update_key(id), id);
+        assert_ne!(AnimeVariant::Dub.update_key(id), id);
+    }
 }
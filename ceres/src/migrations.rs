@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the documents written by this build. Bump it along with a new entry in
+/// `MIGRATIONS` whenever the stored format changes.
+pub const STORAGE_VERSION: u32 = 9;
+
+/// Upgrades a document of the given file from the version of its index to the next one.
+type Migration = fn(file: &str, data: Value) -> Result<Value, String>;
+
+const MIGRATIONS: [Migration; STORAGE_VERSION as usize] = [
+    // 0 → 1: documents get wrapped in an envelope, their content is unchanged
+    |_, data| Ok(data),
+    // 1 → 2: series may have tags, which builds that don't know them would drop when
+    // saving the lists; the content is unchanged
+    |_, data| Ok(data),
+    // 2 → 3: series may have an airing time and chats a timezone; the content is
+    // unchanged
+    |_, data| Ok(data),
+    // 3 → 4: recorded episodes may have the time they were recorded; the content is
+    // unchanged
+    |_, data| Ok(data),
+    // 4 → 5: updates keep a history and chats may have a digest day along with the
+    // last one sent; the content is unchanged
+    |_, data| Ok(data),
+    // 5 → 6: series may have an episode duration; the content is unchanged
+    |_, data| Ok(data),
+    // 6 → 7: series may have a priority; the content is unchanged
+    |_, data| Ok(data),
+    // 7 → 8: series may have the progress of each member; the content is unchanged
+    |_, data| Ok(data),
+    // 8 → 9: finished series may have a score, episode thumbs and a note; the content is
+    // unchanged
+    |_, data| Ok(data),
+];
+
+/// Every stored document is wrapped in an envelope recording its version.
+#[derive(Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T> Envelope<T> {
+    pub fn new(data: T) -> Self {
+        Envelope {
+            version: STORAGE_VERSION,
+            data,
+        }
+    }
+}
+
+/// Version of a stored document; the ones written before the envelope existed are 0.
+pub fn version_of(document: &Value) -> u32 {
+    match envelope_parts(document) {
+        Some((version, _)) => version,
+        None => 0,
+    }
+}
+
+fn envelope_parts(document: &Value) -> Option<(u32, &Value)> {
+    let object = document.as_object()?;
+    if object.len() != 2 {
+        return None;
+    }
+    let version = u32::try_from(object.get("version")?.as_u64()?).ok()?;
+    Some((version, object.get("data")?))
+}
+
+/// Runs the migrations needed to bring a stored document of `file` up to
+/// `STORAGE_VERSION`, returning its data.
+pub fn upgrade(file: &str, document: Value) -> Result<Value, String> {
+    let version = version_of(&document);
+    if version > STORAGE_VERSION {
+        return Err(format!(
+            "{file} has version {version}, newer than the supported {STORAGE_VERSION}"
+        ));
+    }
+    let mut data = match document {
+        Value::Object(mut object) if version > 0 => object.remove("data").unwrap_or_default(),
+        document => document,
+    };
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        data = migration(file, data)
+            .map_err(|e| format!("migrating {file} from version {from}: {e}"))?;
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::{AnimeSeason, Follows, Updates};
+    use crate::storage::{FOLLOWING_FILE, UPDATES_FILE};
+
+    #[test]
+    fn unversioned_documents_upgrade() {
+        // as written before the envelope was introduced
+        let following = r#"{"following": {"098f6bcd4621d373cade4e832627b4f6": {
+            "info": {"name": "Some name", "last_episode": 1},
+            "extra": {"en_name": "Some en name", "season": {"Autumn": 2022}}}}}"#;
+        let document: Value = serde_json::from_str(following).unwrap();
+        assert_eq!(version_of(&document), 0);
+        let data = upgrade(FOLLOWING_FILE, document).unwrap();
+        let following: Follows = serde_json::from_value(data).unwrap();
+        let info = &following.following["098f6bcd4621d373cade4e832627b4f6"];
+        assert_eq!(info.info.last_episode, 1);
+        assert_eq!(info.extra.season, AnimeSeason::Autumn(2022));
+        assert!(info.extra.tags.is_empty());
+
+        let updates = r#"{"updates": {"098f6bcd4621d373cade4e832627b4f6":
+            {"name": "Some en name", "last_episode": 7}}}"#;
+        let data = upgrade(UPDATES_FILE, serde_json::from_str(updates).unwrap()).unwrap();
+        let updates: Updates = serde_json::from_value(data).unwrap();
+        assert_eq!(updates.updates.len(), 1);
+    }
+
+    #[test]
+    fn tags_survive_the_upgrade() {
+        let document = serde_json::json!({"version": 1, "data": {"following": {"a": {
+            "info": {"name": "A", "last_episode": 2},
+            "extra": {"en_name": "A", "season": "Unknown"}}}}});
+        let data = upgrade(FOLLOWING_FILE, document).unwrap();
+        let mut following: Follows = serde_json::from_value(data).unwrap();
+        let info = following.following.get_mut("a").unwrap();
+        assert!(info.extra.tags.is_empty());
+        info.extra.tags.insert("isekai".to_owned());
+
+        let document = serde_json::to_value(Envelope::new(following)).unwrap();
+        assert_eq!(version_of(&document), STORAGE_VERSION);
+        let data = upgrade(FOLLOWING_FILE, document).unwrap();
+        let following: Follows = serde_json::from_value(data).unwrap();
+        assert!(following.following["a"].extra.tags.contains("isekai"));
+    }
+
+    #[test]
+    fn envelopes_round_trip() {
+        let document = serde_json::to_value(Envelope::new(vec![1, 2])).unwrap();
+        assert_eq!(version_of(&document), STORAGE_VERSION);
+        assert_eq!(
+            upgrade("x.json", document).unwrap(),
+            serde_json::json!([1, 2])
+        );
+
+        let newer = serde_json::json!({"version": STORAGE_VERSION + 1, "data": {}});
+        assert!(upgrade("x.json", newer).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardMarkup, MessageId, ParseMode};
+use teloxide::utils::html;
+
+use crate::anime::{AniEpisode, AniInfo, AnimeSeason, AnimeVariant, MAX_SCORE};
+use crate::Result;
+
+// Telegram rejects messages longer than 4096 UTF-16 code units
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// Sends an HTML formatted text, splitting it into several messages if it is too long.
+pub async fn send_html(bot: &Bot, chat_id: ChatId, text: &str) -> Result<()> {
+    for chunk in split_message(text, MESSAGE_LIMIT) {
+        bot.send_message(chat_id, chunk)
+            .parse_mode(ParseMode::Html)
+            .disable_web_page_preview(true)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Sends an HTML formatted text like `send_html`, with a keyboard under its last message.
+pub async fn send_html_with_keyboard(
+    bot: &Bot,
+    chat_id: ChatId,
+    text: &str,
+    keyboard: InlineKeyboardMarkup,
+) -> Result<()> {
+    let mut chunks = split_message(text, MESSAGE_LIMIT);
+    let last = chunks.pop().unwrap_or_default();
+    for chunk in chunks {
+        bot.send_message(chat_id, chunk)
+            .parse_mode(ParseMode::Html)
+            .disable_web_page_preview(true)
+            .await?;
+    }
+    bot.send_message(chat_id, last)
+        .parse_mode(ParseMode::Html)
+        .disable_web_page_preview(true)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// Replaces the text of a message and its inline keyboard.
+pub async fn edit_html_with_keyboard(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    text: &str,
+    keyboard: InlineKeyboardMarkup,
+) -> Result<()> {
+    bot.edit_message_text(chat_id, message_id, text)
+        .parse_mode(ParseMode::Html)
+        .disable_web_page_preview(true)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// Replaces the text of a message, dropping its inline keyboard.
+pub async fn edit_html(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    text: &str,
+) -> Result<()> {
+    bot.edit_message_text(chat_id, message_id, text)
+        .parse_mode(ParseMode::Html)
+        .disable_web_page_preview(true)
+        .await?;
+    Ok(())
+}
+
+/// Splits a text into chunks of at most `limit` UTF-16 code units, cutting at line
+/// boundaries. Lines that don't fit on their own are cut at character boundaries.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    for line in text.split_inclusive('\n') {
+        let line_len = utf16_len(line);
+        if current_len + line_len > limit && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        if line_len <= limit {
+            current.push_str(line);
+            current_len += line_len;
+            continue;
+        }
+        for c in line.chars() {
+            if current_len + c.len_utf16() > limit {
+                chunks.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            current.push(c);
+            current_len += c.len_utf16();
+        }
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// Renders a series as "— <b>name</b> [<i>season</i>] - Ep. N".
+pub fn series_line(aniinfo: &AniInfo) -> String {
+    format!(
+        "— {} [{}]{} - Ep. {}\n",
+        title(&aniinfo.extra.en_name),
+        season(&aniinfo.extra.season.to_string()),
+        variant_tag(aniinfo.extra.variant),
+        aniinfo.info.last_episode
+    )
+}
+
+/// Renders a finished series like `series_line`, followed by our score, the thumbs of
+/// its episodes and our note if there are any.
+pub fn finished_line(aniinfo: &AniInfo) -> String {
+    let mut ret = series_line(aniinfo);
+    let mut rating: Vec<String> = Vec::new();
+    if let Some(score) = aniinfo.extra.score {
+        rating.push(format!("★ {score}/{MAX_SCORE}"));
+    }
+    let liked = aniinfo.extra.thumbs.values().filter(|up| **up).count();
+    let disliked = aniinfo.extra.thumbs.len() - liked;
+    if liked > 0 {
+        rating.push(format!("👍 {liked}"));
+    }
+    if disliked > 0 {
+        rating.push(format!("👎 {disliked}"));
+    }
+    if !rating.is_empty() {
+        ret.push_str(&format!("    {}\n", rating.join(" · ")));
+    }
+    if let Some(note) = &aniinfo.extra.note {
+        ret.push_str(&format!("    📝 {}\n", escape(note)));
+    }
+    ret
+}
+
+/// Renders the series under a heading for each season, the latest season first and the
+/// unknown one last.
+pub fn grouped_by_season(series: &[&AniInfo]) -> String {
+    let mut seasons: Vec<&AnimeSeason> = series.iter().map(|s| &s.extra.season).collect();
+    seasons.sort_unstable_by_key(|s| (**s == AnimeSeason::Unknown, std::cmp::Reverse(*s)));
+    seasons.dedup();
+    let mut ret = String::new();
+    for season in seasons {
+        ret.push_str(&format!("\n{}\n", title(&season.to_string())));
+        for aniinfo in series.iter().filter(|s| s.extra.season == *season) {
+            ret.push_str(&series_line(aniinfo));
+        }
+    }
+    ret
+}
+
+/// Renders the link of an episode along with its publish date, if we know them.
+pub fn episode_link(episode: &AniEpisode) -> Option<String> {
+    let link = episode.link.as_ref()?;
+    // html::link escapes the url itself
+    let mut ret = html::link(link, &format!("Ep. {}", episode.number));
+    if let Some(published) = episode.published {
+        ret.push_str(&format!(" ({})", published.format("%b %d, %H:%M UTC")));
+    }
+    Some(ret)
+}
+
+pub fn escape(text: &str) -> String {
+    html::escape(text)
+}
+
+pub fn code(text: &str) -> String {
+    html::code_inline(text)
+}
+
+pub fn title(name: &str) -> String {
+    html::bold(&html::escape(name))
+}
+
+pub fn season(season: &str) -> String {
+    html::italic(&html::escape(season))
+}
+
+pub fn variant_tag(variant: AnimeVariant) -> String {
+    if variant.is_sub() {
+        String::new()
+    } else {
+        format!(" ({variant})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn split_at_line_boundaries() {
+        let text = "first line\nsecond line\nthird line\n";
+        assert_eq!(split_message(text, 4096), vec![text.to_owned()]);
+        assert_eq!(
+            split_message(text, 24),
+            vec!["first line\nsecond line\n", "third line\n"]
+        );
+        let chunks = split_message(&"a".repeat(10), 4);
+        assert_eq!(chunks, vec!["aaaa", "aaaa", "aa"]);
+        // emojis take two UTF-16 code units
+        assert_eq!(split_message("😀😀\n", 3), vec!["😀", "😀\n"]);
+    }
+
+    #[test]
+    fn escaped_series_line() {
+        let mut aniinfo: AniInfo = serde_json::from_str(
+            r#"{"info": {"name": "a", "last_episode": 3},
+                "extra": {"en_name": "Tom & Jerry <3", "season": {"Autumn": 2022}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            series_line(&aniinfo),
+            "— <b>Tom &amp; Jerry &lt;3</b> [<i>Autumn 2022</i>] - Ep. 3\n"
+        );
+        aniinfo.extra.variant = AnimeVariant::Dub;
+        assert!(series_line(&aniinfo).contains("(dub)"));
+    }
+
+    #[test]
+    fn episode_links() {
+        let link = Some("https://example.org/watch?id=3&ep=12".to_owned());
+        let published = DateTime::parse_from_rfc3339("2024-04-06T14:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let episode = AniEpisode::new(12, link, Some(published));
+        assert_eq!(
+            episode_link(&episode).unwrap(),
+            "<a href=\"https://example.org/watch?id=3&amp;ep=12\">Ep. 12</a> (Apr 06, 14:30 UTC)"
+        );
+        assert_eq!(episode_link(&AniEpisode::new(12, None, None)), None);
+    }
+
+    #[test]
+    fn rated_finished_line() {
+        let mut aniinfo: AniInfo = serde_json::from_str(
+            r#"{"info": {"name": "a", "last_episode": 3},
+                "extra": {"en_name": "A", "season": "Unknown"}}"#,
+        )
+        .unwrap();
+        assert_eq!(finished_line(&aniinfo), series_line(&aniinfo));
+        aniinfo.extra.score = Some(8);
+        aniinfo
+            .extra
+            .thumbs
+            .extend([(1, true), (2, false), (3, true)]);
+        aniinfo.extra.note = Some("watch the <b>dub</b>".to_owned());
+        assert_eq!(
+            finished_line(&aniinfo),
+            "— <b>A</b> [<i>Unknown</i>] - Ep. 3\n    ★ 8/10 · 👍 2 · 👎 1\n    \
+             📝 watch the &lt;b&gt;dub&lt;/b&gt;\n"
+        );
+    }
+
+    #[test]
+    fn season_headings() {
+        let series = |name: &str, season: AnimeSeason| {
+            let mut aniinfo = AniInfo {
+                info: serde_json::from_str(r#"{"name": "a", "last_episode": 1}"#).unwrap(),
+                extra: Default::default(),
+            };
+            aniinfo.extra.en_name = name.to_owned();
+            aniinfo.extra.season = season;
+            aniinfo
+        };
+        let text = grouped_by_season(&[
+            &series("A", AnimeSeason::Unknown),
+            &series("B", AnimeSeason::Winter(2025)),
+            &series("C", AnimeSeason::Autumn(2024)),
+            &series("D", AnimeSeason::Winter(2025)),
+        ]);
+        let headings: Vec<&str> = text.lines().filter(|l| l.starts_with("<b>")).collect();
+        assert_eq!(
+            headings,
+            vec!["<b>Winter 2025</b>", "<b>Autumn 2024</b>", "<b>Unknown</b>"]
+        );
+        assert!(text.contains("<b>Winter 2025</b>\n— <b>B</b>"));
+        assert!(text.contains("— <b>B</b> [<i>Winter 2025</i>] - Ep. 1\n— <b>D</b>"));
+    }
+}
@@ -0,0 +1,299 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::anime::{AniInfo, Follows, Releases, Updates};
+use crate::i18n::{tr, tr_args, Lang};
+use crate::render;
+
+// how long after its airing time an episode may take to show up in the feeds
+const AIRING_GRACE: Duration = Duration::hours(12);
+
+/// Weekly airing slot of a series, in the timezone of its broadcast.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Airing {
+    pub weekday: Weekday,
+    pub time: NaiveTime,
+    pub timezone: Tz,
+}
+
+impl Airing {
+    /// Parses "<weekday> <HH:MM> [timezone]", e.g. "sat 23:30 Asia/Tokyo".
+    pub fn parse(s: &str, default_timezone: Tz) -> Result<Self, String> {
+        let mut parts = s.split_whitespace();
+        let weekday = parts.next().and_then(|w| w.parse::<Weekday>().ok());
+        let time = parts
+            .next()
+            .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok());
+        let timezone = match parts.next() {
+            Some(tz) => tz.parse::<Tz>().ok(),
+            None => Some(default_timezone),
+        };
+        match (weekday, time, timezone, parts.next()) {
+            (Some(weekday), Some(time), Some(timezone), None) => Ok(Airing {
+                weekday,
+                time,
+                timezone,
+            }),
+            _ => Err(s.to_owned()),
+        }
+    }
+
+    /// First airing after `now`.
+    pub fn next(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.with_timezone(&self.timezone).weekday();
+        let days = (7 + self.weekday.num_days_from_monday() - today.num_days_from_monday()) % 7;
+        let date = now.with_timezone(&self.timezone).date_naive() + Duration::days(days.into());
+        let next = self.on(date);
+        if next > now {
+            next
+        } else {
+            self.on(date + Duration::days(7))
+        }
+    }
+
+    /// Last airing up to `now`.
+    pub fn previous(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let next = self.next(now);
+        let date = next.with_timezone(&self.timezone).date_naive() - Duration::days(7);
+        self.on(date)
+    }
+
+    fn on(&self, date: NaiveDate) -> DateTime<Utc> {
+        let local = date.and_time(self.time);
+        self.timezone
+            .from_local_datetime(&local)
+            .earliest()
+            // skipped by a DST change, it airs an hour later on the wall clock
+            .or_else(|| {
+                let later = local + Duration::hours(1);
+                self.timezone.from_local_datetime(&later).earliest()
+            })
+            .map_or_else(|| Utc.from_utc_datetime(&local), |t| t.with_timezone(&Utc))
+    }
+
+    /// Localized "<weekday> <HH:MM> (<timezone>)".
+    pub fn describe(&self, lang: Lang) -> String {
+        format!(
+            "{} {} ({})",
+            weekday_name(lang, self.weekday),
+            self.time.format("%H:%M"),
+            self.timezone.name()
+        )
+    }
+}
+
+/// Localized "<weekday> <HH:MM>" of a time as seen in `timezone`.
+pub fn format_time(lang: Lang, time: DateTime<Utc>, timezone: Tz) -> String {
+    let local = time.with_timezone(&timezone);
+    format!(
+        "{} {}",
+        weekday_name(lang, local.weekday()),
+        local.format("%H:%M")
+    )
+}
+
+pub fn weekday_name(lang: Lang, weekday: Weekday) -> String {
+    let key = format!("weekday-{}", weekday.to_string().to_lowercase());
+    tr(lang, &key)
+}
+
+// "2d 4h", "3h 20m"
+fn countdown(left: Duration) -> String {
+    let (days, hours, minutes) = (
+        left.num_days(),
+        left.num_hours() % 24,
+        left.num_minutes() % 60,
+    );
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else {
+        format!("{hours}h {minutes}m")
+    }
+}
+
+/// Renders the episodes expected over the next week, in the timezone of the chat.
+pub fn week(
+    lang: Lang,
+    timezone: Tz,
+    following: &Follows,
+    updates: &Updates,
+    now: DateTime<Utc>,
+) -> String {
+    let mut expected: Vec<(DateTime<Utc>, &AniInfo, i16)> = Vec::new();
+    for (id, info) in following.following.iter() {
+        let airing = match info.extra.airing {
+            Some(airing) => airing,
+            None => continue,
+        };
+        let notified = updates
+            .updates
+            .get(&info.extra.variant.update_key(id))
+            .map_or(0, |u| u.last_episode);
+        let episode = notified.max(info.info.last_episode) + 1;
+        expected.push((airing.next(now), info, episode));
+    }
+    if expected.is_empty() {
+        return tr(lang, "schedule-empty");
+    }
+    expected.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)));
+    let mut ret = tr_args(
+        lang,
+        "schedule-header",
+        &[("timezone", timezone.name().into())],
+    ) + "\n\n";
+    for (time, info, episode) in expected {
+        let line = tr_args(
+            lang,
+            "schedule-line",
+            &[
+                ("airing", format_time(lang, time, timezone).into()),
+                ("title", render::title(&info.extra.en_name).into()),
+                ("episode", episode.into()),
+                ("countdown", countdown(time - now).into()),
+            ],
+        );
+        ret.push_str(&(line + "\n"));
+    }
+    ret
+}
+
+/// Followed series whose last airing is past the grace period without any episode
+/// published since, along with when it aired. The feeds only list recent releases, so an
+/// episode that we recorded since also counts as published.
+pub fn late_series<'a>(
+    following: &'a Follows,
+    releases: &Releases,
+    updates: &Updates,
+    now: DateTime<Utc>,
+) -> Vec<(&'a AniInfo, DateTime<Utc>)> {
+    let mut late = Vec::new();
+    for (id, info) in following.following.iter() {
+        let aired = match info.extra.airing {
+            Some(airing) => airing.previous(now),
+            None => continue,
+        };
+        if now - aired < AIRING_GRACE {
+            continue;
+        }
+        // releases without a publish date come from the scraper, which only lists
+        // recent ones; those published well before the airing are the previous episode
+        let since = aired - Duration::days(1);
+        let recorded = updates
+            .updates
+            .get(&info.extra.variant.update_key(id))
+            .and_then(|u| u.updated)
+            .is_some_and(|updated| updated > since);
+        let released = recorded
+            || releases
+                .get(&(id.to_owned(), info.extra.variant))
+                .is_some_and(|r| {
+                    r.episodes
+                        .iter()
+                        .any(|e| e.published.is_none_or(|p| p > since))
+                });
+        if !released {
+            late.push((info, aired));
+        }
+    }
+    late.sort_unstable();
+    late
+}
+
+/// Renders the series returned by `late_series`, in the timezone of the chat.
+pub fn late_warning(lang: Lang, timezone: Tz, late: &[(&AniInfo, DateTime<Utc>)]) -> String {
+    let mut ret = tr(lang, "late-header") + "\n";
+    for (info, aired) in late {
+        let line = tr_args(
+            lang,
+            "late-line",
+            &[
+                ("title", render::title(&info.extra.en_name).into()),
+                ("airing", format_time(lang, *aired, timezone).into()),
+            ],
+        );
+        ret.push_str(&(line + "\n"));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::fixtures::at;
+    use crate::anime::{AniEpisode, AniMinInfo, AniRelease, AnimeVariant};
+    use std::collections::HashMap;
+
+    #[test]
+    fn next_and_previous_airing() {
+        let airing = Airing::parse("Sat 23:30 Asia/Tokyo", Tz::UTC).unwrap();
+        assert_eq!(airing.timezone, Tz::Asia__Tokyo);
+        // Saturday 23:30 in Tokyo is Saturday 14:30 UTC
+        let now = at("2024-04-10T12:00:00Z");
+        assert_eq!(airing.next(now), at("2024-04-13T14:30:00Z"));
+        assert_eq!(airing.previous(now), at("2024-04-06T14:30:00Z"));
+        let now = at("2024-04-13T14:30:00Z");
+        assert_eq!(airing.next(now), at("2024-04-20T14:30:00Z"));
+        assert_eq!(airing.previous(now), now);
+        assert_eq!(
+            format_time(Lang::En, now, Tz::Europe__Madrid),
+            "Saturday 16:30"
+        );
+        assert_eq!(airing.describe(Lang::En), "Saturday 23:30 (Asia/Tokyo)");
+        assert!(Airing::parse("someday 23:30", Tz::UTC).is_err());
+        assert!(Airing::parse("sat 25:00", Tz::UTC).is_err());
+        assert_eq!(
+            Airing::parse("sat 09:00", Tz::UTC).unwrap().timezone,
+            Tz::UTC
+        );
+    }
+
+    #[test]
+    fn late_episodes() {
+        let following: Follows = serde_json::from_str(
+            r#"{"following": {
+                "a": {"info": {"name": "A", "last_episode": 3},
+                      "extra": {"en_name": "A", "season": "Unknown", "airing":
+                        {"weekday": "Mon", "time": "10:00:00", "timezone": "UTC"}}},
+                "b": {"info": {"name": "B", "last_episode": 3},
+                      "extra": {"en_name": "B", "season": "Unknown"}}}}"#,
+        )
+        .unwrap();
+        let mut releases: Releases = HashMap::new();
+        let published = Some(at("2024-04-01T12:00:00Z"));
+        releases.insert(
+            ("a".to_owned(), AnimeVariant::Sub),
+            AniRelease::new("A", AniEpisode::new(4, None, published)),
+        );
+        let mut updates = Updates {
+            updates: HashMap::new(),
+            history: Vec::new(),
+        };
+        // a week later the episode of Monday 8 is missing
+        let late = late_series(&following, &releases, &updates, at("2024-04-09T00:00:00Z"));
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0].1, at("2024-04-08T10:00:00Z"));
+        // still within the grace period
+        let now = at("2024-04-08T13:00:00Z");
+        assert!(late_series(&following, &releases, &updates, now).is_empty());
+        // on time
+        let now = at("2024-04-02T00:00:00Z");
+        assert!(late_series(&following, &releases, &updates, now).is_empty());
+
+        // episode 5 was notified by an earlier check and is no longer in the feed
+        let recorded = AniMinInfo {
+            name: "A".to_owned(),
+            last_episode: 5,
+            updated: Some(at("2024-04-08T11:00:00Z")),
+        };
+        updates.updates.insert("a".to_owned(), recorded);
+        let now = at("2024-04-10T00:00:00Z");
+        assert!(late_series(&following, &HashMap::new(), &updates, now).is_empty());
+        // and the next one is late again
+        let now = at("2024-04-16T00:00:00Z");
+        assert_eq!(
+            late_series(&following, &HashMap::new(), &updates, now).len(),
+            1
+        );
+    }
+}
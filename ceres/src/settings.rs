@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use teloxide::types::ChatId;
+use tokio::sync::Mutex;
+
+use crate::i18n::Lang;
+use crate::{storage, Result};
+
+pub static SETTINGS_FILE: &str = "chat-settings.json";
+
+// the settings as we last read or wrote them; it is held while they are changed, so
+// that changes don't overwrite each other
+static SETTINGS: OnceLock<Mutex<Option<Cached>>> = OnceLock::new();
+
+struct Cached {
+    settings: Settings,
+    // when their file was modified as we last read or wrote it
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    // keyed by chat id
+    pub chats: HashMap<i64, ChatSettings>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChatSettings {
+    #[serde(default)]
+    pub language: Lang,
+    // UTC if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
+    // day of the weekly digest, none if it isn't wanted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<Weekday>,
+    // scheduled time of the last weekly digest that we sent, or when it was enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_digest: Option<DateTime<Utc>>,
+}
+
+impl ChatSettings {
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+}
+
+impl Settings {
+    pub fn chat(&self, chat_id: ChatId) -> ChatSettings {
+        self.chats.get(&chat_id.0).cloned().unwrap_or_default()
+    }
+
+    pub fn chat_mut(&mut self, chat_id: ChatId) -> &mut ChatSettings {
+        self.chats.entry(chat_id.0).or_default()
+    }
+}
+
+/// Settings of every chat.
+pub async fn load() -> io::Result<Settings> {
+    let mut cache = SETTINGS.get_or_init(Mutex::default).lock().await;
+    Ok(cached(&mut cache).await?.clone())
+}
+
+/// Applies a change to the settings and stores them.
+pub async fn update<R>(change: impl FnOnce(&mut Settings) -> R) -> Result<R> {
+    let mut cache = SETTINGS.get_or_init(Mutex::default).lock().await;
+    let mut settings = cached(&mut cache).await?.clone();
+    let ret = change(&mut settings);
+    storage::save(SETTINGS_FILE, &settings).await?;
+    *cache = Some(Cached {
+        settings,
+        modified: modified().await,
+    });
+    Ok(ret)
+}
+
+// the cached settings, read again only when someone else changed their file
+async fn cached(cache: &mut Option<Cached>) -> io::Result<&mut Settings> {
+    let modified = modified().await;
+    match cache {
+        Some(cached) if cached.modified != modified => match read().await {
+            Ok(settings) => *cached = Cached { settings, modified },
+            // a broken or half written file keeps the settings that we have
+            Err(e) => log::error!(
+                "keeping the chat settings in memory, unable to reload them: {}",
+                e
+            ),
+        },
+        Some(_) => {}
+        None => {
+            let settings = read().await?;
+            *cache = Some(Cached { settings, modified });
+        }
+    }
+    Ok(&mut cache.as_mut().expect("settings were just read").settings)
+}
+
+// a missing file means that nobody changed them yet
+async fn read() -> io::Result<Settings> {
+    match storage::load(SETTINGS_FILE).await {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+        settings => settings,
+    }
+}
+
+async fn modified() -> Option<SystemTime> {
+    tokio::fs::metadata(storage::path(SETTINGS_FILE))
+        .await
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+/// Language of a chat, falling back to the default one if the settings can't be read.
+pub async fn chat_language(chat_id: ChatId) -> Lang {
+    chat_settings(chat_id).await.language
+}
+
+/// Timezone of a chat, UTC unless it was set or if the settings can't be read.
+pub async fn chat_timezone(chat_id: ChatId) -> Tz {
+    chat_settings(chat_id).await.timezone()
+}
+
+async fn chat_settings(chat_id: ChatId) -> ChatSettings {
+    let mut cache = SETTINGS.get_or_init(Mutex::default).lock().await;
+    match cached(&mut cache).await {
+        Ok(settings) => settings.chat(chat_id),
+        Err(e) => {
+            log::error!("unable to read the chat settings: {}", e);
+            ChatSettings::default()
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::Mutex;
+
+use crate::anime::{series_id, AniInfo, Follows, Updates};
+use crate::storage::{self, FINISHED_FILE, FOLLOWING_FILE, UPDATES_FILE};
+
+/// The following, finished and updates lists.
+#[derive(Debug, Clone)]
+pub struct Lists {
+    pub following: Follows,
+    pub finished: Follows,
+    pub updates: Updates,
+}
+
+impl Lists {
+    async fn load(dir: &Path) -> io::Result<Lists> {
+        let mut lists = Lists {
+            following: storage::load_from(dir, FOLLOWING_FILE).await?,
+            finished: storage::load_from(dir, FINISHED_FILE).await?,
+            updates: storage::load_from(dir, UPDATES_FILE).await?,
+        };
+        lists.recover();
+        Ok(lists)
+    }
+
+    /// Finishing a series writes the finished list before the following one, so a
+    /// series in both lists was being finished when the bot stopped; complete it.
+    fn recover(&mut self) {
+        let finished = &self.finished.following;
+        self.following.following.retain(|id, _| {
+            let interrupted = finished.contains_key(id);
+            if interrupted {
+                log::warn!("completing the interrupted finish of {}", id);
+            }
+            !interrupted
+        });
+    }
+
+    /// Followed or finished series given their id or a name: an exact name wins over the
+    /// names that merely contain it, which may be several.
+    pub fn find(&self, query: &str) -> Vec<(&String, &AniInfo)> {
+        let query = query.trim();
+        let all = || {
+            self.following
+                .following
+                .iter()
+                .chain(self.finished.following.iter())
+        };
+        let id = series_id(query);
+        let exact: Vec<(&String, &AniInfo)> = all()
+            .filter(|(k, info)| {
+                **k == query
+                    || **k == id
+                    || info.extra.en_name.eq_ignore_ascii_case(query)
+                    || info.info.name.eq_ignore_ascii_case(query)
+            })
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        let query = query.to_lowercase();
+        let mut found: Vec<(&String, &AniInfo)> = all()
+            .filter(|(_, info)| {
+                info.extra.en_name.to_lowercase().contains(&query)
+                    || info.info.name.to_lowercase().contains(&query)
+            })
+            .collect();
+        found.sort_unstable_by(|a, b| a.1.cmp(b.1));
+        found
+    }
+
+    /// Followed or finished series with the given id.
+    pub fn series_mut(&mut self, id: &str) -> Option<&mut AniInfo> {
+        self.following
+            .following
+            .get_mut(id)
+            .or(self.finished.following.get_mut(id))
+    }
+
+    /// Tags of our series, with how many series have each of them.
+    pub fn tags(&self) -> BTreeMap<&str, usize> {
+        let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+        let all = self
+            .following
+            .following
+            .values()
+            .chain(self.finished.following.values());
+        for info in all {
+            for tag in &info.extra.tags {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+        tags
+    }
+
+    // in the order in which they are written
+    fn documents(&self) -> io::Result<[(&'static str, Value); 3]> {
+        Ok([
+            (FINISHED_FILE, serde_json::to_value(&self.finished)?),
+            (FOLLOWING_FILE, serde_json::to_value(&self.following)?),
+            (UPDATES_FILE, serde_json::to_value(&self.updates)?),
+        ])
+    }
+}
+
+/// Single owner of the lists. Every read and change goes through it, so concurrent
+/// handlers can't overwrite each other's changes. The lists are kept in memory and read
+/// again from the storage once its files change.
+pub struct State {
+    // storage directory of the lists
+    dir: PathBuf,
+    // None until the lists are first needed
+    cache: Mutex<Option<Cached>>,
+    // set by the watcher when the files were edited outside of the bot
+    stale: AtomicBool,
+}
+
+// the lists along with when their files were modified as we last read or wrote them
+struct Cached {
+    lists: Lists,
+    modified: Modified,
+}
+
+type Modified = [Option<SystemTime>; 3];
+
+async fn modified(dir: &Path) -> Modified {
+    let mut ret: Modified = [None; 3];
+    for (time, file) in ret
+        .iter_mut()
+        .zip([FOLLOWING_FILE, FINISHED_FILE, UPDATES_FILE])
+    {
+        *time = tokio::fs::metadata(dir.join(file))
+            .await
+            .and_then(|m| m.modified())
+            .ok();
+    }
+    ret
+}
+
+impl State {
+    /// State of the lists stored in the storage directory.
+    pub fn new() -> Arc<State> {
+        State::in_dir(storage::dir())
+    }
+
+    /// State of the lists stored in another directory.
+    pub fn in_dir(dir: PathBuf) -> Arc<State> {
+        Arc::new(State {
+            dir,
+            cache: Mutex::new(None),
+            stale: AtomicBool::new(false),
+        })
+    }
+
+    /// Watches the storage directory, dropping the cached lists whenever one of their
+    /// files changes. Watching stops when the returned watcher is dropped.
+    pub fn watch(self: &Arc<Self>) -> notify::Result<RecommendedWatcher> {
+        let state = Arc::downgrade(self);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::error!("error watching the storage: {}", e);
+                        return;
+                    }
+                };
+                if event.kind.is_access() || !event.paths.iter().any(|p| is_lists_file(p)) {
+                    return;
+                }
+                if let Some(state) = state.upgrade() {
+                    state.stale.store(true, Ordering::SeqCst);
+                }
+            })?;
+        // the files are replaced on every save, so we watch their directory instead
+        watcher.watch(&self.dir, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    }
+
+    /// Current lists.
+    pub async fn snapshot(&self) -> io::Result<Lists> {
+        let mut cache = self.cache.lock().await;
+        Ok(self.cached(&mut cache).await?.to_owned())
+    }
+
+    /// Applies a change to the lists and stores the files that it touched, all of them
+    /// or none if something fails before they are replaced.
+    pub async fn update<R>(&self, change: impl FnOnce(&mut Lists) -> R) -> io::Result<R> {
+        let mut cache = self.cache.lock().await;
+        let lists = self.cached(&mut cache).await?;
+        let before = lists.documents()?;
+        let mut changed = lists.to_owned();
+        let ret = change(&mut changed);
+        let documents: Vec<(&str, Value)> = changed
+            .documents()?
+            .into_iter()
+            .zip(before)
+            .filter(|(after, before)| after.1 != before.1)
+            .map(|(after, _)| after)
+            .collect();
+        storage::save_all(&self.dir, &documents).await?;
+        *cache = Some(Cached {
+            lists: changed,
+            modified: modified(&self.dir).await,
+        });
+        Ok(ret)
+    }
+
+    // the cached lists, loading them if they are missing or stale
+    async fn cached<'a>(&self, cache: &'a mut Option<Cached>) -> io::Result<&'a mut Lists> {
+        let stale = self.stale.swap(false, Ordering::SeqCst);
+        if let Some(cached) = cache.as_mut().filter(|_| stale) {
+            // our own saves wake the watcher up too, those leave the times that we recorded
+            let modified = modified(&self.dir).await;
+            if modified != cached.modified {
+                // a broken or half written file keeps the lists that we have, the
+                // watcher tells us again once it is written
+                match Lists::load(&self.dir).await {
+                    Ok(lists) => *cached = Cached { lists, modified },
+                    Err(e) => {
+                        log::error!("keeping the lists in memory, unable to reload them: {}", e)
+                    }
+                }
+            }
+        }
+        if cache.is_none() {
+            let modified = modified(&self.dir).await;
+            let lists = Lists::load(&self.dir).await?;
+            *cache = Some(Cached { lists, modified });
+        }
+        Ok(&mut cache.as_mut().expect("lists were just loaded").lists)
+    }
+}
+
+fn is_lists_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .is_some_and(|f| [FOLLOWING_FILE, FINISHED_FILE, UPDATES_FILE].contains(&f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::fixtures::follows;
+    use crate::anime::{normalize_tag, AniMinInfo, TAG_MAX_CHARS};
+    use std::collections::HashMap;
+
+    #[test]
+    fn interrupted_finish_is_completed() {
+        let info = serde_json::from_str(
+            r#"{"info": {"name": "Monster", "last_episode": 74},
+                "extra": {"en_name": "Monster", "season": "Unknown"}}"#,
+        )
+        .unwrap();
+        let mut lists = Lists {
+            following: Follows {
+                following: HashMap::from([("a".to_owned(), info)]),
+            },
+            finished: Follows {
+                following: HashMap::new(),
+            },
+            updates: Updates {
+                updates: HashMap::new(),
+                history: Vec::new(),
+            },
+        };
+        lists.recover();
+        assert_eq!(lists.following.following.len(), 1);
+        lists.finished.following = lists.following.following.to_owned();
+        lists.recover();
+        assert!(lists.following.following.is_empty());
+        assert_eq!(lists.finished.following.len(), 1);
+    }
+
+    #[test]
+    fn tags_are_counted() {
+        let lists = Lists {
+            following: follows(
+                r#"{"following": {
+                    "a": {"info": {"name": "A", "last_episode": 1},
+                          "extra": {"en_name": "A", "season": "Unknown",
+                                    "tags": ["isekai", "watch with ana"]}},
+                    "b": {"info": {"name": "B", "last_episode": 1},
+                          "extra": {"en_name": "B", "season": "Unknown"}}}}"#,
+            ),
+            finished: follows(
+                r#"{"following": {"c": {"info": {"name": "C", "last_episode": 1},
+                    "extra": {"en_name": "C", "season": "Unknown", "tags": ["isekai"]}}}}"#,
+            ),
+            updates: Updates {
+                updates: HashMap::new(),
+                history: Vec::new(),
+            },
+        };
+        assert_eq!(
+            lists.tags(),
+            BTreeMap::from([("isekai", 2), ("watch with ana", 1)])
+        );
+        assert_eq!(
+            normalize_tag("  Watch with\tAna ").as_deref(),
+            Some("watch with ana")
+        );
+        assert_eq!(normalize_tag(" "), None);
+        assert_eq!(normalize_tag(&"a".repeat(TAG_MAX_CHARS + 1)), None);
+    }
+
+    #[test]
+    fn series_are_found_by_name() {
+        let lists = Lists {
+            following: follows(
+                r#"{"following": {
+                    "a": {"info": {"name": "Kimi no Na wa", "last_episode": 1},
+                          "extra": {"en_name": "Your Name", "season": "Unknown"}},
+                    "b": {"info": {"name": "Kimi ni Todoke", "last_episode": 1},
+                          "extra": {"en_name": "From Me to You", "season": "Unknown"}}}}"#,
+            ),
+            finished: follows(
+                r#"{"following": {"c": {"info": {"name": "Name", "last_episode": 1},
+                    "extra": {"en_name": "Name", "season": "Unknown"}}}}"#,
+            ),
+            updates: Updates {
+                updates: HashMap::new(),
+                history: Vec::new(),
+            },
+        };
+        let ids = |query: &str| -> Vec<&str> {
+            lists
+                .find(query)
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect()
+        };
+        assert_eq!(ids("b"), ["b"]);
+        assert_eq!(ids("your name"), ["a"]);
+        assert_eq!(ids("kimi"), ["b", "a"]);
+        // an exact name wins over the ones that contain it
+        assert_eq!(ids(" name "), ["c"]);
+        assert!(ids("monster").is_empty());
+    }
+
+    #[tokio::test]
+    async fn own_saves_are_not_reloaded() -> io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("storage-own-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await?;
+        for (file, content) in [
+            (FOLLOWING_FILE, r#"{"following": {}}"#),
+            (FINISHED_FILE, r#"{"following": {}}"#),
+            (UPDATES_FILE, r#"{"updates": {}}"#),
+        ] {
+            tokio::fs::write(dir.join(file), content).await?;
+        }
+        let state = State::in_dir(dir.to_owned());
+        let _watcher = state.watch().unwrap();
+        let info: AniMinInfo = serde_json::from_str(r#"{"name": "B", "last_episode": 1}"#)?;
+        state
+            .update(|lists| lists.updates.updates.insert("b".to_owned(), info))
+            .await?;
+        // only the times tell our saves apart: a file that keeps the time of our save
+        // is ours, whatever it contains
+        let path = dir.join(UPDATES_FILE);
+        let saved = std::fs::metadata(&path)?.modified()?;
+        std::fs::write(&path, r#"{"updates": {}}"#)?;
+        std::fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(saved)?;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let lists = state.snapshot().await;
+        tokio::fs::remove_dir_all(dir).await?;
+        assert!(lists?.updates.updates.contains_key("b"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn external_edits_are_reloaded() -> io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("storage-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await?;
+        for (file, content) in [
+            (FOLLOWING_FILE, r#"{"following": {}}"#),
+            (FINISHED_FILE, r#"{"following": {}}"#),
+            (UPDATES_FILE, r#"{"updates": {}}"#),
+        ] {
+            tokio::fs::write(dir.join(file), content).await?;
+        }
+        let state = State::in_dir(dir.to_owned());
+        let _watcher = state.watch().unwrap();
+        let before = state.snapshot().await;
+
+        let edited = r#"{"updates": {"a": {"name": "A", "last_episode": 2}}}"#;
+        tokio::fs::write(dir.join(UPDATES_FILE), edited).await?;
+        let mut reloaded = false;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            if state.snapshot().await?.updates.updates.contains_key("a") {
+                reloaded = true;
+                break;
+            }
+        }
+        tokio::fs::write(dir.join(UPDATES_FILE), r#"{"updates": {"#).await?;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let broken = state.snapshot().await;
+        // clean up before asserting so that a failure doesn't leave the directory behind
+        tokio::fs::remove_dir_all(dir).await?;
+        assert!(before?.updates.updates.is_empty());
+        assert!(reloaded);
+        // the broken edit is ignored
+        assert!(broken?.updates.updates.contains_key("a"));
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::anime::AnimeSeason;
+use crate::i18n::{tr, tr_args, Lang};
+use crate::render;
+use crate::state::Lists;
+
+/// Renders how much we have watched, how our series spread over the seasons and what is
+/// left to watch.
+pub fn render(lang: Lang, lists: &Lists) -> String {
+    let series = || {
+        lists
+            .following
+            .following
+            .values()
+            .chain(lists.finished.following.values())
+    };
+    let episodes: i64 = series().map(|s| i64::from(s.info.last_episode)).sum();
+    let minutes: i64 = series()
+        .map(|s| i64::from(s.info.last_episode) * i64::from(s.episode_minutes()))
+        .sum();
+    let mut ret = tr(lang, "stats-header") + "\n\n";
+    ret.push_str(&tr_args(
+        lang,
+        "stats-series",
+        &[
+            ("following", lists.following.following.len().into()),
+            ("finished", lists.finished.following.len().into()),
+        ],
+    ));
+    ret.push('\n');
+    ret.push_str(&tr_args(
+        lang,
+        "stats-watched",
+        &[
+            ("episodes", episodes.into()),
+            ("hours", hours(minutes).into()),
+        ],
+    ));
+    ret.push('\n');
+
+    let mut seasons: BTreeMap<&AnimeSeason, usize> = BTreeMap::new();
+    for info in series() {
+        *seasons.entry(&info.extra.season).or_default() += 1;
+    }
+    let mut seasons: Vec<(&AnimeSeason, usize)> = seasons.into_iter().collect();
+    seasons.sort_unstable_by_key(|(s, _)| (**s == AnimeSeason::Unknown, Reverse(*s)));
+    if !seasons.is_empty() {
+        ret.push_str(&format!("\n{}\n", tr(lang, "stats-seasons")));
+        for (season, count) in seasons {
+            ret.push_str(&format!(
+                "— {}: {count}\n",
+                render::season(&season.to_string())
+            ));
+        }
+    }
+
+    let backlog = lists.following.backlog(&lists.updates);
+    ret.push('\n');
+    if backlog.series == 0 {
+        ret.push_str(&tr(lang, "stats-backlog-empty"));
+    } else {
+        ret.push_str(&tr_args(
+            lang,
+            "stats-backlog",
+            &[
+                ("episodes", backlog.episodes.into()),
+                ("series", backlog.series.into()),
+                ("hours", hours(backlog.minutes).into()),
+            ],
+        ));
+    }
+    ret
+}
+
+/// Rounds minutes to the nearest hour.
+pub fn hours(minutes: i64) -> i64 {
+    (minutes + 30) / 60
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::fixtures::follows;
+    use crate::anime::Updates;
+
+    #[test]
+    fn watch_statistics() {
+        let lists = Lists {
+            following: follows(
+                r#"{"following": {
+                    "a": {"info": {"name": "A", "last_episode": 4},
+                          "extra": {"en_name": "A", "season": {"Spring": 2024}}},
+                    "b": {"info": {"name": "B", "last_episode": 10},
+                          "extra": {"en_name": "B", "season": "Unknown", "duration": 12}}}}"#,
+            ),
+            finished: follows(
+                r#"{"following": {"c": {"info": {"name": "C", "last_episode": 12},
+                    "extra": {"en_name": "C", "season": {"Winter": 2025}}}}}"#,
+            ),
+            updates: serde_json::from_str::<Updates>(
+                r#"{"updates": {"a": {"name": "A", "last_episode": 9}}}"#,
+            )
+            .unwrap(),
+        };
+        assert_eq!(
+            render(Lang::En, &lists),
+            "Our statistics:\n\n\
+             Following 2 series and finished 1.\n\
+             We watched 26 episodes, about 8 hours.\n\
+             \nSeries per season:\n\
+             — <i>Winter 2025</i>: 1\n\
+             — <i>Spring 2024</i>: 1\n\
+             — <i>Unknown</i>: 1\n\
+             \nLeft to watch: 5 episodes of 1 series, about 2 hours."
+        );
+    }
+}
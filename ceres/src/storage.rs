@@ -0,0 +1,171 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use teloxide::dispatching::dialogue::Storage;
+use teloxide::types::ChatId;
+use tokio::sync::Mutex;
+
+use crate::migrations::{self, Envelope};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+pub static FOLLOWING_FILE: &str = "anime-following.json";
+pub static FINISHED_FILE: &str = "anime-finished.json";
+pub static UPDATES_FILE: &str = "anime-updates.json";
+pub static DIALOGUES_FILE: &str = "dialogues.json";
+
+/// The storage directory, given by BOT_STORAGE.
+pub fn dir() -> PathBuf {
+    let store_dir = env::var("BOT_STORAGE").expect("Error checking BOT_STORAGE");
+    PathBuf::from(store_dir)
+}
+
+/// Path of a file of the storage directory.
+pub fn path(file: &str) -> PathBuf {
+    dir().join(file)
+}
+
+/// Reads a JSON file of the storage.
+pub async fn load<T: DeserializeOwned>(file: &str) -> io::Result<T> {
+    load_from(&dir(), file).await
+}
+
+/// Reads a JSON file of the given storage directory.
+pub async fn load_from<T: DeserializeOwned>(dir: &Path, file: &str) -> io::Result<T> {
+    let content = tokio::fs::read(dir.join(file)).await?;
+    decode(file, &content)
+}
+
+/// Writes a JSON file of the storage.
+pub async fn save<T: Serialize>(file: &str, value: &T) -> io::Result<()> {
+    write_json(&path(file), value).await
+}
+
+/// Writes several JSON files of the given storage directory, in the order in which they
+/// have to be replaced. None of them is touched unless all of them could be written.
+pub async fn save_all<T: Serialize>(dir: &Path, files: &[(&str, T)]) -> io::Result<()> {
+    let mut written = Vec::new();
+    for (file, value) in files {
+        let path = dir.join(file);
+        let tmp = path.with_extension("tmp");
+        let content = serde_json::to_string_pretty(&Envelope::new(value))?;
+        if let Err(e) = tokio::fs::write(&tmp, content).await {
+            for (tmp, _) in written {
+                let _ = tokio::fs::remove_file(tmp).await;
+            }
+            return Err(e);
+        }
+        written.push((tmp, path));
+    }
+    for (tmp, path) in written {
+        tokio::fs::rename(&tmp, path).await?;
+    }
+    Ok(())
+}
+
+/// Deserializes a stored document, migrating it first if an older version wrote it.
+fn decode<T: DeserializeOwned>(file: &str, content: &[u8]) -> io::Result<T> {
+    let document: serde_json::Value = serde_json::from_slice(content)?;
+    let data = migrations::upgrade(file, document)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(serde_json::from_value(data)?)
+}
+
+// write and rename so that a crash never leaves a truncated file behind
+async fn write_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let content = serde_json::to_string_pretty(&Envelope::new(value))?;
+    tokio::fs::write(&tmp, content).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// Dialogue storage backed by a JSON file, so that conversations survive bot restarts.
+pub struct FileStorage<D> {
+    path: PathBuf,
+    // keyed by chat id
+    map: Mutex<HashMap<i64, D>>,
+}
+
+impl<D: Serialize + DeserializeOwned> FileStorage<D> {
+    /// Opens the storage at `path`, loading the dialogues that were stored there.
+    pub async fn open(path: PathBuf) -> io::Result<Arc<Self>> {
+        let file = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or_default();
+        let map = match tokio::fs::read(&path).await {
+            Ok(content) => decode(file, &content)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Arc::new(FileStorage {
+            path,
+            map: Mutex::new(map),
+        }))
+    }
+
+    async fn persist(&self, map: &HashMap<i64, D>) -> io::Result<()> {
+        write_json(&self.path, map).await
+    }
+}
+
+impl<D> Storage<D> for FileStorage<D>
+where
+    D: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    type Error = io::Error;
+
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<io::Result<()>> {
+        Box::pin(async move {
+            let mut map = self.map.lock().await;
+            if map.remove(&chat_id.0).is_some() {
+                self.persist(&map).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn update_dialogue(self: Arc<Self>, chat_id: ChatId, dialogue: D) -> BoxFuture<io::Result<()>> {
+        Box::pin(async move {
+            let mut map = self.map.lock().await;
+            map.insert(chat_id.0, dialogue);
+            self.persist(&map).await
+        })
+    }
+
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> BoxFuture<io::Result<Option<D>>> {
+        Box::pin(async move { Ok(self.map.lock().await.get(&chat_id.0).cloned()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dialogues_survive_reopening() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("dialogues-{}.json", std::process::id()));
+        let storage = FileStorage::<String>::open(path.to_owned()).await?;
+        storage
+            .clone()
+            .update_dialogue(ChatId(1), "finishing".to_owned())
+            .await?;
+        storage
+            .update_dialogue(ChatId(2), "updating".to_owned())
+            .await?;
+        let reopened = FileStorage::<String>::open(path.to_owned()).await?;
+        reopened.clone().remove_dialogue(ChatId(2)).await?;
+        assert_eq!(
+            reopened.clone().get_dialogue(ChatId(1)).await?,
+            Some("finishing".to_owned())
+        );
+        assert_eq!(reopened.get_dialogue(ChatId(2)).await?, None);
+        tokio::fs::remove_file(path).await
+    }
+}
@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::anime::{AniInfo, Change, Follows, SortBy, Updates};
+use crate::i18n::{tr, tr_args, Lang};
+use crate::{render, stats};
+
+/// Order of the to-watch list: any of the lists, or one that only makes sense for what
+/// is pending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchOrder {
+    List(SortBy),
+    // the series whose first pending episode was notified the longest ago first
+    Oldest,
+    // fewest episodes left first
+    Remaining,
+    // most urgent first, then the oldest
+    Priority,
+}
+
+impl WatchOrder {
+    const PENDING: [WatchOrder; 3] = [
+        WatchOrder::Oldest,
+        WatchOrder::Remaining,
+        WatchOrder::Priority,
+    ];
+
+    /// Comma separated list of the orders, for help messages.
+    pub fn available() -> String {
+        let pending = WatchOrder::PENDING.map(|o| o.to_string()).join(", ");
+        format!("{}, {pending}", SortBy::available())
+    }
+
+    pub fn compare(&self, a: &Pending, b: &Pending, updates: &Updates) -> Ordering {
+        // unknown dates last
+        let since = |p: &Pending| (p.since.is_none(), p.since);
+        let order = match self {
+            WatchOrder::List(sort) => return sort.compare((a.id, a.info), (b.id, b.info), updates),
+            WatchOrder::Oldest => since(a).cmp(&since(b)),
+            WatchOrder::Remaining => a.remaining().cmp(&b.remaining()),
+            WatchOrder::Priority => a
+                .info
+                .extra
+                .priority
+                .cmp(&b.info.extra.priority)
+                .then(since(a).cmp(&since(b))),
+        };
+        order.then(a.info.cmp(b.info))
+    }
+}
+
+impl Default for WatchOrder {
+    fn default() -> Self {
+        WatchOrder::List(SortBy::default())
+    }
+}
+
+impl Display for WatchOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchOrder::List(sort) => write!(f, "{sort}"),
+            WatchOrder::Oldest => write!(f, "oldest"),
+            WatchOrder::Remaining => write!(f, "remaining"),
+            WatchOrder::Priority => write!(f, "priority"),
+        }
+    }
+}
+
+impl FromStr for WatchOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WatchOrder::PENDING
+            .into_iter()
+            .find(|o| o.to_string().eq_ignore_ascii_case(s.trim()))
+            .map_or_else(|| s.parse::<SortBy>().map(WatchOrder::List), Ok)
+    }
+}
+
+/// A followed series with released episodes that we haven't watched.
+#[derive(Debug)]
+pub struct Pending<'a> {
+    pub id: &'a str,
+    pub info: &'a AniInfo,
+    // last episode released
+    pub released: i16,
+    // when the first episode that we haven't watched was notified, if we know it
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl Pending<'_> {
+    pub fn remaining(&self) -> i16 {
+        self.released - self.info.info.last_episode
+    }
+}
+
+pub fn pending<'a>(following: &'a Follows, updates: &Updates) -> Vec<Pending<'a>> {
+    let mut pending = Vec::new();
+    for (id, info) in following.following.iter() {
+        let remaining = info.unwatched(id, updates);
+        if remaining == 0 {
+            continue;
+        }
+        pending.push(Pending {
+            id,
+            info,
+            released: info.info.last_episode + remaining,
+            since: pending_since(id, info, updates),
+        });
+    }
+    pending
+}
+
+// the history knows when each episode was notified, otherwise we only know it of the
+// last one
+fn pending_since(id: &str, info: &AniInfo, updates: &Updates) -> Option<DateTime<Utc>> {
+    let first = info.info.last_episode + 1;
+    updates
+        .history
+        .iter()
+        .find(|e| {
+            e.id == id
+                && matches!(&e.change, Change::Released { episodes }
+                    if episodes.iter().any(|&n| n >= first))
+        })
+        .map(|e| e.time)
+        .or_else(|| {
+            updates
+                .updates
+                .get(&info.extra.variant.update_key(id))
+                .and_then(|u| u.updated)
+        })
+}
+
+/// Renders the to-watch list in the given order, with what it adds up to at the top.
+pub fn render(
+    lang: Lang,
+    mut pending: Vec<Pending>,
+    order: WatchOrder,
+    updates: &Updates,
+) -> String {
+    if pending.is_empty() {
+        return tr(lang, "towatch-empty");
+    }
+    pending.sort_unstable_by(|a, b| order.compare(a, b, updates));
+    let episodes: i64 = pending.iter().map(|p| i64::from(p.remaining())).sum();
+    let minutes: i64 = pending
+        .iter()
+        .map(|p| i64::from(p.remaining()) * i64::from(p.info.episode_minutes()))
+        .sum();
+    let mut ret = tr(lang, "towatch-header") + "\n";
+    ret.push_str(&tr_args(
+        lang,
+        "towatch-totals",
+        &[
+            ("episodes", episodes.into()),
+            ("series", pending.len().into()),
+            ("hours", stats::hours(minutes).into()),
+        ],
+    ));
+    ret.push_str("\n\n");
+    for p in pending {
+        let desc = if p.remaining() == 1 {
+            tr_args(lang, "towatch-single", &[("episode", p.released.into())])
+        } else {
+            tr_args(
+                lang,
+                "towatch-range",
+                &[
+                    ("from", (p.info.info.last_episode + 1).into()),
+                    ("to", p.released.into()),
+                ],
+            )
+        };
+        ret.push_str(&format!(
+            "· {}\n    → {desc}\n",
+            render::title(&p.info.extra.en_name)
+        ));
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anime::fixtures::at;
+    use crate::anime::Priority;
+
+    #[test]
+    fn backlog_orders() {
+        let mut following: Follows = serde_json::from_str(
+            r#"{"following": {
+                "a": {"info": {"name": "A", "last_episode": 1},
+                      "extra": {"en_name": "A", "season": "Unknown"}},
+                "b": {"info": {"name": "B", "last_episode": 5},
+                      "extra": {"en_name": "B", "season": "Unknown"}},
+                "c": {"info": {"name": "C", "last_episode": 2},
+                      "extra": {"en_name": "C", "season": "Unknown"}},
+                "d": {"info": {"name": "D", "last_episode": 3},
+                      "extra": {"en_name": "D", "season": "Unknown"}}}}"#,
+        )
+        .unwrap();
+        following.following.get_mut("c").unwrap().extra.priority = Priority::High;
+        let mut updates: Updates = serde_json::from_str(
+            r#"{"updates": {
+                "a": {"name": "A", "last_episode": 4, "updated": "2024-04-20T00:00:00Z"},
+                "b": {"name": "B", "last_episode": 6, "updated": "2024-04-10T00:00:00Z"},
+                "c": {"name": "C", "last_episode": 4},
+                "d": {"name": "D", "last_episode": 3}}}"#,
+        )
+        .unwrap();
+        // episode 2 of A was notified well before the last one
+        let released = Change::Released { episodes: vec![2] };
+        updates.record("a", released, at("2024-04-01T00:00:00Z"));
+        let order = |order: &str| -> Vec<&str> {
+            let order = order.parse::<WatchOrder>().unwrap();
+            let mut pending = pending(&following, &updates);
+            pending.sort_unstable_by(|a, b| order.compare(a, b, &updates));
+            pending.iter().map(|p| p.id).collect()
+        };
+        assert_eq!(order("name"), ["a", "b", "c"]);
+        assert_eq!(order("oldest"), ["a", "b", "c"]);
+        assert_eq!(order("remaining"), ["b", "c", "a"]);
+        assert_eq!(order("priority"), ["c", "a", "b"]);
+        assert!("colour".parse::<WatchOrder>().is_err());
+
+        let text = render(
+            Lang::En,
+            pending(&following, &updates),
+            WatchOrder::default(),
+            &updates,
+        );
+        assert!(text.contains("6 episodes of 3 series, about 2 hours."));
+    }
+}